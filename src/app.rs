@@ -2,30 +2,89 @@
 
 use cpal::traits::StreamTrait;
 use eframe::egui;
-use enigo::{Enigo, Key, Keyboard, Settings, Direction};
-use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
 use std::sync::mpsc::{channel, Receiver, Sender};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 
-use crate::audio::{self, RecordingState};
-use crate::settings::AppSettings;
-use crate::transcriber::Transcriber;
+use crate::audio::{self, AudioChunk, AudioError, ChunkSink, InputDeviceInfo, ProcessingOptions, RecordingState};
+use crate::config::QUEUE_RETRY_SECS;
+use crate::diff::{self, DiffPart};
+use crate::history::{self, HistoryRecord, HistoryStore, RecordingContext};
+use crate::hotkey::{Binding, Hotkey};
+use crate::injector::{self, Injector};
+use crate::listener;
+use crate::queue;
+use crate::refiner::builtin_modes;
+use crate::settings::{validate_base_url, validate_endpoint, validate_model, AppSettings, AudioEncoding, InjectorKind, RefineMode, RefinerKind, ResamplerKind, SpeechBackendKind, TriggerMode};
+use crate::retry::RetryObserver;
+use crate::transcriber::{Segment, Transcriber, TranscriptionError, TranscriptionResponse};
 
-use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
-    GetAsyncKeyState, VK_F8, VK_LSHIFT, VK_LWIN, VK_RSHIFT, VK_RWIN,
-};
+#[cfg(windows)]
+use windows_sys::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowTextW, SetForegroundWindow};
 
 const RELEASE_GRACE_MS: u64 = 250;
+/// Shorter release debounce for the double-tap trigger, so the gap between taps is not swallowed
+const TAP_RELEASE_GRACE_MS: u64 = 40;
+/// A press shorter than this is a tap; a second tap within this long locks the recording
+const DOUBLE_TAP_MS: u64 = 400;
 const KEY_STATE_IDLE: u8 = 0;
 const KEY_STATE_PRESSED: u8 = 1;
 const KEY_STATE_RELEASED: u8 = 2;
+const HISTORY_PAGE_SIZE: usize = 10;
 
 enum AppMessage {
     TranscriptionStart,
-    TranscriptionComplete(String),
-    TranscriptionError(String),
+    /// Raw text of the chunks transcribed so far in streaming mode
+    PartialTranscript(String),
+    /// Progress line from a background request, e.g. a pending retry
+    Status(String),
+    TranscriptionComplete(TranscriptionResponse),
+    TranscriptionError(TranscriptionError),
+    /// The service was unreachable and the recording was saved to the offline queue
+    Queued(TranscriptionError),
+    /// A queued recording finally went through
+    DelayedComplete(TranscriptionResponse, RecordingContext),
+    /// A queued recording failed for a reason retrying cannot fix and was set aside
+    QueueEntryFailed(TranscriptionError),
+    /// A queue retry pass ended with this many recordings still waiting
+    QueueFlushed(usize),
+}
+
+/// Which binding the key-capture widget is recording
+#[derive(Clone, Copy, PartialEq)]
+enum BindingTarget {
+    Main(usize),
+    NewMain,
+    Mode(usize),
+    Cancel,
+    /// Paste shortcut of the current injector
+    Paste,
+}
+
+/// A message plus the recording it belongs to; `None` for work not tied to one, like flushing the queue
+type TaggedMessage = (Option<u64>, AppMessage);
+
+/// Sender for one recording's background work; goes quiet once that recording is cancelled
+#[derive(Clone)]
+struct JobSender {
+    tx: Sender<TaggedMessage>,
+    job: u64,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl JobSender {
+    fn send(&self, message: AppMessage) {
+        if !self.is_cancelled() {
+            let _ = self.tx.send((Some(self.job), message));
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
 }
 
 #[derive(PartialEq, Clone, Copy)]
@@ -38,8 +97,16 @@ enum AppState {
 
 pub struct VoxMagicApp {
     state: AppState,
-    history: Vec<String>,
+    history: HistoryStore,
+    history_query: String,
+    history_page: usize,
+    /// Id of the history record showing its details
+    expanded_entry: Option<u64>,
+    /// Raw-vs-refined diff of the expanded record, computed once rather than every frame
+    diff_layout: Option<(u64, egui::text::LayoutJob)>,
     status_message: String,
+    /// Text streamed in while a streaming-mode recording is still going
+    live_text: String,
 
     // UI Animations
     pulse_start: Instant,
@@ -47,19 +114,57 @@ pub struct VoxMagicApp {
     show_settings: bool,
     show_help: bool,
     settings: AppSettings,
+    input_devices: Vec<InputDeviceInfo>,
 
-    message_rx: Receiver<AppMessage>,
-    message_tx: Sender<AppMessage>,
+    message_rx: Receiver<TaggedMessage>,
+    message_tx: Sender<TaggedMessage>,
 
     recording_state: RecordingState,
     active_stream: Option<cpal::Stream>,
+    streaming: bool,
+
+    queued_count: usize,
+    queue_flushing: bool,
+    last_queue_attempt: Instant,
 
     hotkey_state: Arc<AtomicU8>,
+    /// How long all keys must be up before the hotkey thread reports a release
+    release_grace_ms: Arc<AtomicU64>,
+    /// Double-tap lock engaged: keep recording until the next tap
+    hands_free: bool,
+    /// When a short tap was released, while waiting to see if a second tap locks the recording
+    pending_tap: Option<Instant>,
+    /// Every hotkey with its refinement mode, polled by the hotkey thread
+    bindings: Arc<Mutex<Vec<Binding>>>,
+    /// Mode whose key started the current press; `None` for the main hotkey
+    pressed_mode: Arc<Mutex<Option<String>>>,
+    /// Refinement mode override for the recording in progress
+    recording_mode: Option<String>,
+    /// Polled by the hotkey thread, which raises `cancel_requested` when it is pressed
+    cancel_hotkey: Arc<Mutex<Option<Hotkey>>>,
+    cancel_requested: Arc<AtomicBool>,
+    /// Raised to silence the background work of the current recording
+    job_cancelled: Arc<AtomicBool>,
+    /// Bumped for every recording; messages tagged with an older one are ignored
+    job_id: u64,
+    /// Set while the hotkey thread records a key combination for the binding widget
+    capturing: Arc<AtomicBool>,
+    captured: Arc<Mutex<Option<Hotkey>>>,
+    /// Any modifier key held, as last seen by the hotkey thread
+    modifiers_held: Arc<AtomicBool>,
+    binding_target: Option<BindingTarget>,
     _app_is_running: Arc<AtomicBool>,
     recording_start_time: Option<Instant>,
+    /// Window that had focus when the current recording started
+    target_window: Option<isize>,
+    /// Window the text of each record made this session was pasted into, for "paste raw instead"
+    record_windows: HashMap<u64, isize>,
+    /// Details of the recording being transcribed, stored with its history record
+    recording_context: RecordingContext,
 
     clipboard: Option<arboard::Clipboard>,
-    enigo: Enigo,
+    /// Sends the paste shortcut; `None` when no backend works, and text is then only copied
+    injector: Option<Box<dyn Injector>>,
     logo_texture: Option<egui::TextureHandle>,
 }
 
@@ -68,87 +173,202 @@ impl VoxMagicApp {
         let (message_tx, message_rx) = channel();
         let settings = AppSettings::load();
 
-        let enigo = Enigo::new(&Settings::default()).unwrap_or_else(|_| {
-            panic!("Failed to initialize Enigo");
-        });
+        let injector = injector::create_injector(settings.injector)
+            .inspect_err(|e| println!("⚠️ Auto-paste unavailable: {}", e))
+            .ok();
 
         let hotkey_state = Arc::new(AtomicU8::new(KEY_STATE_IDLE));
+        let release_grace_ms = Arc::new(AtomicU64::new(release_grace(settings.trigger_mode)));
         let app_is_running = Arc::new(AtomicBool::new(true));
 
+        let bindings = Arc::new(Mutex::new(settings.bindings()));
+        let pressed_mode = Arc::new(Mutex::new(None));
+        let cancel_hotkey = Arc::new(Mutex::new(settings.cancel_hotkey));
+        let cancel_requested = Arc::new(AtomicBool::new(false));
+        let capturing = Arc::new(AtomicBool::new(false));
+        let captured = Arc::new(Mutex::new(None));
+        let modifiers_held = Arc::new(AtomicBool::new(false));
+
         let hotkey_state_clone = hotkey_state.clone();
+        let release_grace_clone = release_grace_ms.clone();
         let app_is_running_clone = app_is_running.clone();
+        let bindings_clone = bindings.clone();
+        let pressed_mode_clone = pressed_mode.clone();
+        let cancel_hotkey_clone = cancel_hotkey.clone();
+        let cancel_requested_clone = cancel_requested.clone();
+        let capturing_clone = capturing.clone();
+        let captured_clone = captured.clone();
+        let modifiers_held_clone = modifiers_held.clone();
+
+        let (listener, listener_error) = match listener::create_listener() {
+            Ok(listener) => (Some(listener), None),
+            Err(e) => {
+                println!("⚠️ Global hotkeys unavailable: {}", e);
+                (None, Some(e))
+            }
+        };
 
         thread::spawn(move || {
-            let mut was_pressed = false;
+            let Some(mut listener) = listener else { return };
+            let mut active: Option<Hotkey> = None;
             let mut release_start: Option<Instant> = None;
+            let mut capture = Hotkey::default();
+            let mut cancel_was_down = false;
 
             while app_is_running_clone.load(Ordering::Relaxed) {
-                unsafe {
-                    let f8_down = (GetAsyncKeyState(VK_F8 as i32) as u16 & 0x8000) != 0;
-                    let lshift_down = (GetAsyncKeyState(VK_LSHIFT as i32) as u16 & 0x8000) != 0;
-                    let rshift_down = (GetAsyncKeyState(VK_RSHIFT as i32) as u16 & 0x8000) != 0;
-                    let lwin_down = (GetAsyncKeyState(VK_LWIN as i32) as u16 & 0x8000) != 0;
-                    let rwin_down = (GetAsyncKeyState(VK_RWIN as i32) as u16 & 0x8000) != 0;
-
-                    let is_pressed = f8_down || ((lshift_down || rshift_down) && (lwin_down || rwin_down));
+                thread::sleep(Duration::from_millis(10));
+                let keyboard = listener.poll();
+                modifiers_held_clone.store(keyboard.any_modifier(), Ordering::Relaxed);
 
-                    if is_pressed {
-                        release_start = None;
-                        if !was_pressed {
-                            hotkey_state_clone.store(KEY_STATE_PRESSED, Ordering::SeqCst);
-                            was_pressed = true;
-                        }
-                    } else if was_pressed {
-                        if release_start.is_none() {
-                            release_start = Some(Instant::now());
+                // While binding a key, collect everything held and hand the combo over once all keys are up
+                if capturing_clone.load(Ordering::SeqCst) {
+                    let held = Hotkey::from_state(&keyboard);
+                    if !held.is_empty() {
+                        capture = capture.merge(held);
+                    } else if !capture.is_empty() {
+                        if let Ok(mut captured) = captured_clone.lock() {
+                            *captured = Some(std::mem::take(&mut capture));
                         }
+                        capturing_clone.store(false, Ordering::SeqCst);
+                    }
+                    continue;
+                }
 
-                        if let Some(start) = release_start {
-                            if start.elapsed().as_millis() >= RELEASE_GRACE_MS as u128 {
-                                hotkey_state_clone.store(KEY_STATE_RELEASED, Ordering::SeqCst);
-                                was_pressed = false;
-                                release_start = None;
+                let cancel = cancel_hotkey_clone.lock().ok().and_then(|hotkey| *hotkey);
+                let cancel_down = cancel.is_some_and(|hotkey| hotkey.is_down(&keyboard));
+                if cancel_down && !cancel_was_down {
+                    cancel_requested_clone.store(true, Ordering::SeqCst);
+                }
+                cancel_was_down = cancel_down;
+
+                match active {
+                    None => {
+                        let bindings = bindings_clone.lock().map(|b| b.clone()).unwrap_or_default();
+                        // The most specific match wins so Ctrl+F8 is not also read as F8
+                        let hit = bindings
+                            .into_iter()
+                            .filter(|(hotkey, _)| hotkey.is_down(&keyboard))
+                            .max_by_key(|(hotkey, _)| hotkey.modifier_count());
+                        if let Some((hotkey, mode)) = hit {
+                            if let Ok(mut pressed) = pressed_mode_clone.lock() {
+                                *pressed = mode;
                             }
+                            active = Some(hotkey);
+                            hotkey_state_clone.store(KEY_STATE_PRESSED, Ordering::SeqCst);
+                        }
+                    }
+                    Some(hotkey) if hotkey.is_down(&keyboard) => {
+                        release_start = None;
+                    }
+                    Some(_) => {
+                        let start = *release_start.get_or_insert_with(Instant::now);
+                        if start.elapsed().as_millis() >= release_grace_clone.load(Ordering::Relaxed) as u128 {
+                            hotkey_state_clone.store(KEY_STATE_RELEASED, Ordering::SeqCst);
+                            active = None;
+                            release_start = None;
                         }
                     }
                 }
-                thread::sleep(Duration::from_millis(10));
             }
         });
 
         Self {
             state: AppState::Ready,
-            history: Vec::new(),
-            status_message: if settings.groq_api_key.is_empty() { "⚠️ Setup Required" } else { "Ready" }.to_string(),
+            history: HistoryStore::open(settings.history_retention_days),
+            history_query: String::new(),
+            history_page: 0,
+            expanded_entry: None,
+            diff_layout: None,
+            live_text: String::new(),
+            status_message: match listener_error {
+                Some(e) => format!("⚠️ Hotkeys unavailable: {}", e),
+                None if settings.needs_setup() => "⚠️ Setup Required".to_string(),
+                None => "Ready".to_string(),
+            },
             pulse_start: Instant::now(),
-            show_settings: settings.groq_api_key.is_empty(),
-            show_help: settings.groq_api_key.is_empty(),
+            show_settings: settings.needs_setup(),
+            show_help: settings.needs_setup(),
             settings,
+            input_devices: audio::list_input_devices().unwrap_or_default(),
             message_rx,
             message_tx,
             recording_state: RecordingState::new(),
             active_stream: None,
+            streaming: false,
+            queued_count: queue::list().len(),
+            queue_flushing: false,
+            last_queue_attempt: Instant::now(),
             hotkey_state,
+            release_grace_ms,
+            hands_free: false,
+            pending_tap: None,
+            bindings,
+            pressed_mode,
+            recording_mode: None,
+            cancel_hotkey,
+            cancel_requested,
+            job_cancelled: Arc::new(AtomicBool::new(false)),
+            job_id: 0,
+            capturing,
+            captured,
+            modifiers_held,
+            binding_target: None,
             _app_is_running: app_is_running,
             recording_start_time: None,
+            target_window: None,
+            record_windows: HashMap::new(),
+            recording_context: RecordingContext::default(),
             clipboard: arboard::Clipboard::new().ok(),
-            enigo,
+            injector,
             logo_texture: None,
         }
     }
 
     fn start_recording(&mut self) {
-        if self.state != AppState::Ready || self.settings.groq_api_key.is_empty() {
-            if self.settings.groq_api_key.is_empty() { self.show_settings = true; }
+        if self.state != AppState::Ready || self.settings.needs_setup() {
+            if self.settings.needs_setup() { self.show_settings = true; }
             return;
         }
 
-        match audio::start_recording(&self.recording_state) {
-            Ok(stream) => {
-                self.active_stream = Some(stream);
+        if let Err(e) = self.settings.validate() {
+            self.status_message = format!("⚠️ {}", e);
+            self.show_settings = true;
+            return;
+        }
+
+        // In streaming mode a worker transcribes chunks as the drain thread cuts them
+        let (chunk_sink, chunk_rx) = match self.settings.chunker_config() {
+            Some(config) => {
+                let (chunk_tx, chunk_rx) = channel();
+                (Some(ChunkSink { config, tx: chunk_tx }), Some(chunk_rx))
+            }
+            None => (None, None),
+        };
+
+        match audio::start_recording(&self.recording_state, &self.settings.input_device, chunk_sink) {
+            Ok(recording) => {
+                self.active_stream = Some(recording.stream);
+                self.streaming = chunk_rx.is_some();
+                self.live_text.clear();
+                self.recording_mode = self.pressed_mode.lock().ok().and_then(|mut pressed| pressed.take());
+                self.job_cancelled = Arc::new(AtomicBool::new(false));
+                self.job_id += 1;
+                self.target_window = foreground_window();
+                if let Some(chunk_rx) = chunk_rx {
+                    let transcriber = self.make_transcriber();
+                    let options = self.settings.processing_options();
+                    let tx = self.job_sender();
+                    // Used if part of the recording has to be queued; the duration is filled in then
+                    let context = self.make_context(0);
+                    thread::spawn(move || run_streaming_worker(chunk_rx, transcriber, options, context, tx));
+                }
                 self.state = AppState::Listening;
                 self.recording_start_time = Some(Instant::now());
-                self.status_message = "Listening...".to_string();
+                self.status_message = match (recording.missing_device, &self.recording_mode) {
+                    (Some(name), _) => format!("Listening on default mic (\"{}\" not found)", name),
+                    (None, Some(mode)) => format!("Listening ({})...", mode),
+                    (None, None) => "Listening...".to_string(),
+                };
             }
             Err(e) => {
                 self.status_message = format!("Mic Error: {}", e);
@@ -158,116 +378,490 @@ impl VoxMagicApp {
 
     fn stop_recording(&mut self) {
         if self.state != AppState::Listening { return; }
+        self.hands_free = false;
+        self.pending_tap = None;
 
         if let Some(stream) = self.active_stream.take() {
             let _ = stream.pause();
             drop(stream);
         }
 
+        let duration_ms = self.recording_start_time.take().map(|t| t.elapsed().as_millis() as u64).unwrap_or_default();
+        self.recording_context = self.make_context(duration_ms);
+
+        if self.streaming {
+            self.streaming = false;
+            // The worker finishes the remaining chunks and reports the combined result
+            match audio::stop_streaming(&self.recording_state) {
+                Ok(()) => {
+                    self.status_message = "Finishing...".to_string();
+                    self.state = AppState::Transcribing;
+                }
+                Err(e) => {
+                    // Whatever the worker still sends for this recording is dropped
+                    self.job_cancelled.store(true, Ordering::SeqCst);
+                    self.reject_recording(e);
+                }
+            }
+            return;
+        }
+
+        let audio_data = match audio::stop_recording(&self.recording_state, &self.settings.processing_options()) {
+            Ok(data) => data,
+            Err(e) => {
+                self.reject_recording(e);
+                return;
+            }
+        };
+
         self.state = AppState::Transcribing;
         self.status_message = "Refining...".to_string();
 
-        let audio_result = audio::stop_recording(&self.recording_state);
-        let tx = self.message_tx.clone();
-        let api_key = self.settings.groq_api_key.clone();
+        let tx = self.job_sender();
+        let transcriber = self.make_transcriber();
+        let context = self.recording_context.clone();
 
         thread::spawn(move || {
-            match audio_result {
-                Ok(audio_data) => {
-                    let _ = tx.send(AppMessage::TranscriptionStart);
-                    let transcriber = Transcriber::new(api_key);
-                    match transcriber.transcribe(audio_data) {
-                        Ok(result) => {
-                            let _ = tx.send(AppMessage::TranscriptionComplete(result.text));
-                        }
-                        Err(e) => {
-                            let _ = tx.send(AppMessage::TranscriptionError(format!("{}", e)));
-                        }
-                    }
+            tx.send(AppMessage::TranscriptionStart);
+            match transcriber.transcribe(audio_data.clone()) {
+                Ok(result) => {
+                    tx.send(AppMessage::TranscriptionComplete(result));
                 }
+                Err(_) if tx.is_cancelled() => {}
+                Err(e) if e.is_transient() => match queue::enqueue(&audio_data, &e, context) {
+                    Ok(_) => { tx.send(AppMessage::Queued(e)); }
+                    Err(io_error) => {
+                        println!("⚠️ Could not queue recording: {}", io_error);
+                        tx.send(AppMessage::TranscriptionError(e));
+                    }
+                },
                 Err(e) => {
-                    let _ = tx.send(AppMessage::TranscriptionError(format!("{}", e)));
+                    tx.send(AppMessage::TranscriptionError(e));
+                }
+            }
+        });
+    }
+
+    /// Explain why a finished recording is not being transcribed and go back to Ready
+    fn reject_recording(&mut self, error: AudioError) {
+        self.status_message = match error {
+            AudioError::TooShort(_) => match self.settings.trigger_mode {
+                TriggerMode::PushToTalk => "Too short - hold the hotkey while you speak".to_string(),
+                TriggerMode::Toggle | TriggerMode::DoubleTapLock => "Too short - speak for longer before stopping".to_string(),
+            },
+            AudioError::Silent(_) => "Only silence heard - check your mic".to_string(),
+            e => format!("Mic Error: {}", e),
+        };
+        self.state = AppState::Ready;
+    }
+
+    /// Retry queued offline recordings in order, stopping at the first one that hits a transient failure
+    fn flush_queue(&mut self) {
+        if self.queue_flushing || self.settings.needs_setup() { return; }
+        self.queue_flushing = true;
+        self.last_queue_attempt = Instant::now();
+
+        let tx = self.message_tx.clone();
+        let settings = self.settings.clone();
+        thread::spawn(move || {
+            for mut entry in queue::list() {
+                let audio_data = match entry.load_audio() {
+                    Ok(data) => data,
+                    Err(e) => {
+                        println!("⚠️ Dropping unreadable queued recording {}: {}", entry.id, e);
+                        let _ = entry.remove();
+                        continue;
+                    }
+                };
+
+                // Refine with the mode the recording was made in, not whatever is selected now
+                let mut entry_settings = settings.clone();
+                if let Some(mode) = &entry.context.refine_mode {
+                    entry_settings.refine_mode = mode.clone();
+                }
+                let transcriber = Transcriber::from_settings(&entry_settings, None);
+
+                match transcriber.transcribe(audio_data) {
+                    Ok(result) => {
+                        let _ = entry.remove();
+                        let _ = tx.send((None, AppMessage::DelayedComplete(result, entry.context.clone())));
+                    }
+                    Err(e) if e.is_transient() => {
+                        println!("⏳ Queued recording {} still failing: {}", entry.id, e);
+                        let _ = entry.record_failure(&e);
+                        break;
+                    }
+                    Err(e) => {
+                        let _ = entry.record_failure(&e);
+                        match entry.set_aside() {
+                            Ok(dir) => println!("🚫 Queued recording {} failed ({}), moved to {}", entry.id, e, dir.display()),
+                            Err(io_error) => {
+                                println!("⚠️ Could not set aside queued recording {}: {}", entry.id, io_error);
+                                let _ = entry.remove();
+                            }
+                        }
+                        let _ = tx.send((None, AppMessage::QueueEntryFailed(e)));
+                    }
                 }
             }
+            let _ = tx.send((None, AppMessage::QueueFlushed(queue::list().len())));
         });
     }
 
+    /// Abort the current recording or transcription; nothing is pasted, saved or queued
+    fn cancel_recording(&mut self) {
+        if matches!(self.state, AppState::Ready | AppState::Pasting) {
+            return;
+        }
+        // A transcription thread cannot be interrupted mid-request, so its results are dropped instead.
+        // Raised first so nothing the workers send from here on reaches the UI.
+        self.job_cancelled.store(true, Ordering::SeqCst);
+
+        if self.state == AppState::Listening {
+            if let Some(stream) = self.active_stream.take() {
+                let _ = stream.pause();
+                drop(stream);
+            }
+            if let Err(e) = audio::discard_recording(&self.recording_state) {
+                println!("⚠️ Could not stop recording: {}", e);
+            }
+        }
+
+        self.streaming = false;
+        self.hands_free = false;
+        self.pending_tap = None;
+        self.recording_start_time = None;
+        self.recording_mode = None;
+        self.target_window = None;
+        self.recording_context = RecordingContext::default();
+        self.live_text.clear();
+        self.state = AppState::Ready;
+        self.status_message = "Cancelled - nothing was pasted".to_string();
+        println!("🚫 Recording cancelled");
+    }
+
+    fn job_sender(&self) -> JobSender {
+        JobSender { tx: self.message_tx.clone(), job: self.job_id, cancelled: self.job_cancelled.clone() }
+    }
+
+    /// What is known about the current recording, for history and the offline queue
+    fn make_context(&self, duration_ms: u64) -> RecordingContext {
+        RecordingContext {
+            duration_ms,
+            model: self.settings.speech_model(),
+            target_app: self.target_window.and_then(window_title),
+            refine_mode: Some(self.recording_mode.clone().unwrap_or_else(|| self.settings.refine_mode.clone())),
+        }
+    }
+
+    /// Transcriber for the current settings and recording mode that reports retries in the status line
+    fn make_transcriber(&self) -> Transcriber {
+        let tx = self.job_sender();
+        let observer: RetryObserver = Arc::new(move |message| {
+            tx.send(AppMessage::Status(message));
+        });
+        match &self.recording_mode {
+            Some(mode) => {
+                let mut settings = self.settings.clone();
+                settings.refine_mode = mode.clone();
+                Transcriber::from_settings(&settings, Some(observer))
+            }
+            None => Transcriber::from_settings(&self.settings, Some(observer)),
+        }
+    }
+
+    fn main_hotkey_label(&self) -> String {
+        self.settings.hotkeys.first().map(Hotkey::to_string).unwrap_or_else(|| "the hotkey".to_string())
+    }
+
+    /// Hand the hotkeys and trigger mode from settings to the hotkey thread
+    fn sync_bindings(&self) {
+        if let Ok(mut bindings) = self.bindings.lock() {
+            *bindings = self.settings.bindings();
+        }
+        if let Ok(mut cancel) = self.cancel_hotkey.lock() {
+            *cancel = self.settings.cancel_hotkey;
+        }
+        self.release_grace_ms.store(release_grace(self.settings.trigger_mode), Ordering::Relaxed);
+    }
+
+    /// Rebuild the injector after the backend was changed in settings
+    fn reload_injector(&mut self) {
+        self.injector = match injector::create_injector(self.settings.injector) {
+            Ok(injector) => Some(injector),
+            Err(e) => {
+                self.status_message = format!("⚠️ Auto-paste unavailable: {}", e);
+                None
+            }
+        };
+    }
+
+    /// Backend that pastes right now, which `InjectorKind::Auto` resolves to once created
+    fn injector_kind(&self) -> InjectorKind {
+        self.injector.as_ref().map_or(self.settings.injector, |injector| injector.kind())
+    }
+
+    fn paste_shortcut(&self) -> Hotkey {
+        self.settings.paste_shortcut(self.injector_kind())
+    }
+
+    /// Ask the hotkey thread for the next key combination pressed
+    fn start_capture(&mut self, target: BindingTarget) {
+        if let Ok(mut captured) = self.captured.lock() {
+            *captured = None;
+        }
+        self.binding_target = Some(target);
+        self.capturing.store(true, Ordering::SeqCst);
+    }
+
+    fn cancel_capture(&mut self) {
+        self.binding_target = None;
+        self.capturing.store(false, Ordering::SeqCst);
+    }
+
+    /// Store a captured combination in its slot unless it is invalid or already bound elsewhere
+    fn apply_captured_hotkey(&mut self) {
+        let Some(target) = self.binding_target else { return };
+        let Some(hotkey) = self.captured.lock().ok().and_then(|mut captured| captured.take()) else { return };
+        self.binding_target = None;
+
+        let mut updated = self.settings.clone();
+        match target {
+            BindingTarget::Main(i) if i < updated.hotkeys.len() => updated.hotkeys[i] = hotkey,
+            BindingTarget::NewMain => updated.hotkeys.push(hotkey),
+            BindingTarget::Mode(i) if i < updated.refine_modes.len() => updated.refine_modes[i].hotkey = Some(hotkey),
+            BindingTarget::Cancel => updated.cancel_hotkey = Some(hotkey),
+            BindingTarget::Paste if hotkey.key.is_none() => {
+                self.status_message = "⚠️ The paste shortcut needs a key".to_string();
+                return;
+            }
+            BindingTarget::Paste => {
+                updated.paste_shortcuts.insert(self.injector_kind(), hotkey);
+            }
+            _ => return,
+        }
+
+        match updated.validate_hotkeys() {
+            Ok(()) => {
+                self.settings = updated;
+                let _ = self.settings.save();
+                self.sync_bindings();
+                self.status_message = format!("Bound {}", hotkey);
+            }
+            Err(e) => {
+                self.status_message = format!("⚠️ {}", e);
+            }
+        }
+    }
+
     fn process_hotkey(&mut self) {
-        let state = self.hotkey_state.load(Ordering::SeqCst);
-        match state {
-            KEY_STATE_PRESSED => {
-                self.hotkey_state.store(KEY_STATE_IDLE, Ordering::SeqCst);
-                self.start_recording();
+        if self.cancel_requested.swap(false, Ordering::SeqCst) {
+            self.cancel_recording();
+        }
+
+        let event = self.hotkey_state.swap(KEY_STATE_IDLE, Ordering::SeqCst);
+        let listening = self.state == AppState::Listening;
+        match (self.settings.trigger_mode, event) {
+            (TriggerMode::PushToTalk, KEY_STATE_PRESSED) => self.start_recording(),
+            (TriggerMode::PushToTalk, KEY_STATE_RELEASED) => self.stop_recording(),
+            (TriggerMode::Toggle, KEY_STATE_PRESSED) => {
+                if listening { self.stop_recording(); } else { self.start_recording(); }
             }
-            KEY_STATE_RELEASED => {
-                self.hotkey_state.store(KEY_STATE_IDLE, Ordering::SeqCst);
-                self.stop_recording();
+            (TriggerMode::DoubleTapLock, KEY_STATE_PRESSED) => {
+                if !listening {
+                    self.start_recording();
+                } else if self.pending_tap.take().is_some() {
+                    self.hands_free = true;
+                    self.status_message = format!("🔒 Hands-free - tap {} to finish", self.main_hotkey_label());
+                } else if self.hands_free {
+                    self.stop_recording();
+                }
+            }
+            (TriggerMode::DoubleTapLock, KEY_STATE_RELEASED) if listening && !self.hands_free => {
+                let held = self.recording_start_time.map(|t| t.elapsed()).unwrap_or_default();
+                if held < Duration::from_millis(DOUBLE_TAP_MS) {
+                    self.pending_tap = Some(Instant::now());
+                } else {
+                    self.stop_recording();
+                }
             }
             _ => {}
         }
+
+        // A single tap that was not followed by a second one ends the recording
+        if self.pending_tap.is_some_and(|tap| tap.elapsed() >= Duration::from_millis(DOUBLE_TAP_MS)) {
+            self.stop_recording();
+        }
+
+        let limit = self.settings.max_recording_secs;
+        let elapsed = self.recording_start_time.map(|t| t.elapsed()).unwrap_or_default();
+        if self.state == AppState::Listening && limit > 0 && elapsed >= Duration::from_secs(limit as u64) {
+            println!("⏱️ Recording reached the {} s limit", limit);
+            self.stop_recording();
+            if self.state == AppState::Transcribing {
+                self.status_message = format!("Time limit of {} s reached - refining...", limit);
+            }
+        }
     }
 
     fn process_messages(&mut self) {
-        while let Ok(msg) = self.message_rx.try_recv() {
+        while let Ok((job, msg)) = self.message_rx.try_recv() {
+            // Sent before the recording was cancelled, or by an earlier one
+            if job.is_some_and(|job| job != self.job_id || self.job_cancelled.load(Ordering::SeqCst)) {
+                continue;
+            }
             match msg {
                 AppMessage::TranscriptionStart => { self.state = AppState::Transcribing; }
-                AppMessage::TranscriptionComplete(text) => {
-                    let cleaned_text = text.trim().to_string();
+                AppMessage::PartialTranscript(text) => { self.live_text = text; }
+                AppMessage::Status(message) => { self.status_message = message; }
+                AppMessage::TranscriptionComplete(result) => {
+                    self.live_text.clear();
+                    let cleaned_text = result.text.trim().to_string();
+                    let context = std::mem::take(&mut self.recording_context);
                     if !cleaned_text.is_empty() {
+                        let status = match &result.refine_error {
+                            Some(error) => format!("Used raw text - {}", error_guidance(error)),
+                            None => "Success".to_string(),
+                        };
                         // FIX DOUBLING: History log is read-only
-                        self.history.insert(0, cleaned_text.clone());
-                        if self.history.len() > 10 { self.history.pop(); }
+                        let id = self.record_history(result, context, false);
+                        if let Some(window) = self.target_window.take() {
+                            self.record_windows.insert(id, window);
+                        }
 
-                        if self.settings.auto_paste {
+                        let pasted = if self.settings.auto_paste {
                             self.state = AppState::Pasting;
                             self.status_message = "Pasting...".to_string();
-                            self.paste_text_to_active_window(&cleaned_text);
-                        }
+                            self.paste_text_to_active_window(&cleaned_text)
+                        } else {
+                            Ok(())
+                        };
 
-                        self.status_message = "Success".to_string();
+                        self.status_message = match pasted {
+                            Ok(()) => status,
+                            Err(message) => message,
+                        };
                     } else {
                         self.status_message = "No speech detected".to_string();
                     }
                     self.state = AppState::Ready;
                 }
+                AppMessage::Queued(error) => {
+                    self.live_text.clear();
+                    self.queued_count += 1;
+                    self.last_queue_attempt = Instant::now();
+                    self.status_message = format!("Saved offline ({} queued) - {}", self.queued_count, error_guidance(&error));
+                    self.state = AppState::Ready;
+                }
+                AppMessage::DelayedComplete(result, context) => {
+                    if !result.text.trim().is_empty() {
+                        self.record_history(result, context, true);
+                    }
+                    self.queued_count = self.queued_count.saturating_sub(1);
+                    if self.state == AppState::Ready {
+                        self.status_message = "Delayed recording transcribed".to_string();
+                    }
+                }
+                AppMessage::QueueEntryFailed(error) => {
+                    self.queued_count = self.queued_count.saturating_sub(1);
+                    self.status_message = format!("A queued recording was set aside - {}", error_guidance(&error));
+                }
+                AppMessage::QueueFlushed(remaining) => {
+                    self.queued_count = remaining;
+                    self.queue_flushing = false;
+                }
                 AppMessage::TranscriptionError(error) => {
-                    self.status_message = format!("Error: {}", error);
+                    self.live_text.clear();
+                    self.status_message = error_guidance(&error);
+                    if matches!(error, TranscriptionError::Unauthorized(_)) {
+                        self.show_settings = true;
+                        self.show_help = false;
+                    }
                     self.state = AppState::Ready;
                 }
             }
         }
     }
 
-    fn paste_text_to_active_window(&mut self, text: &str) {
-        let start = Instant::now();
-        unsafe {
-            while start.elapsed() < Duration::from_millis(800) {
-                let lwin = (GetAsyncKeyState(VK_LWIN as i32) as u16 & 0x8000) != 0;
-                let rwin = (GetAsyncKeyState(VK_RWIN as i32) as u16 & 0x8000) != 0;
-                let lshift = (GetAsyncKeyState(VK_LSHIFT as i32) as u16 & 0x8000) != 0;
-                let rshift = (GetAsyncKeyState(VK_RSHIFT as i32) as u16 & 0x8000) != 0;
-                if !lwin && !rwin && !lshift && !rshift { break; }
-                thread::sleep(Duration::from_millis(20));
-            }
+    /// Store a finished transcription and return its record id
+    fn record_history(&mut self, result: TranscriptionResponse, context: RecordingContext, delayed: bool) -> u64 {
+        let id = self.history.next_id();
+        let refined_text = result.text.trim().to_string();
+        let record = HistoryRecord {
+            id,
+            raw_text: result.raw_text.map(|raw| raw.trim().to_string()).unwrap_or_else(|| refined_text.clone()),
+            refined_text,
+            language: result.language,
+            context,
+            delayed,
+            segments: result.segments,
+        };
+        if let Err(e) = self.history.add(record) {
+            println!("⚠️ Could not save history: {}", e);
         }
+        self.history_page = 0;
+        self.expanded_entry = None;
+        id
+    }
 
-        if let Some(ref mut clipboard) = self.clipboard {
-            if clipboard.set_text(text.to_string()).is_ok() {
-                thread::sleep(Duration::from_millis(150));
-                let _ = self.enigo.key(Key::Control, Direction::Press);
-                thread::sleep(Duration::from_millis(30));
-                let _ = self.enigo.key(Key::Unicode('v'), Direction::Click);
-                thread::sleep(Duration::from_millis(30));
-                let _ = self.enigo.key(Key::Control, Direction::Release);
+    /// Paste the unrefined text back into the window the record was dictated into, or copy it if that window is unknown
+    fn paste_raw(&mut self, id: u64) {
+        let Some(raw_text) = self.history.get(id).map(|r| r.raw_text.clone()) else { return };
+
+        match self.record_windows.get(&id) {
+            Some(&window) if focus_window(window) => {
+                thread::sleep(Duration::from_millis(100));
+                self.status_message = match self.paste_text_to_active_window(&raw_text) {
+                    Ok(()) => "Pasted raw text".to_string(),
+                    Err(message) => message,
+                };
+            }
+            _ => {
+                let copied = self.clipboard.as_mut().is_some_and(|c| c.set_text(raw_text).is_ok());
+                self.status_message = if copied {
+                    format!("Raw text copied - paste it with {}", self.paste_shortcut())
+                } else {
+                    "Clipboard unavailable".to_string()
+                };
             }
         }
     }
+
+    /// Copy `text` and send the paste shortcut; the error is a status line telling the user what to do instead
+    fn paste_text_to_active_window(&mut self, text: &str) -> Result<(), String> {
+        // Modifiers still held from the hotkey would turn the paste shortcut into another one
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_millis(800) {
+            if !self.modifiers_held.load(Ordering::Relaxed) { break; }
+            thread::sleep(Duration::from_millis(20));
+        }
+
+        let shortcut = self.paste_shortcut();
+        let copied = self.clipboard.as_mut().is_some_and(|c| c.set_text(text.to_string()).is_ok());
+        if !copied {
+            return Err("Clipboard unavailable - nothing was pasted".to_string());
+        }
+
+        thread::sleep(Duration::from_millis(150));
+        let result = match self.injector.as_mut() {
+            Some(injector) => injector.press(shortcut),
+            None => Err("no paste backend".to_string()),
+        };
+        result.map_err(|e| {
+            println!("⚠️ Could not send {}: {}", shortcut, e);
+            format!("Text copied - paste it with {}", shortcut)
+        })
+    }
 }
 
 impl eframe::App for VoxMagicApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         self.process_hotkey();
+        self.apply_captured_hotkey();
         self.process_messages();
+        if self.queued_count > 0 && self.last_queue_attempt.elapsed() >= Duration::from_secs(QUEUE_RETRY_SECS) {
+            self.flush_queue();
+        }
         ctx.request_repaint_after(Duration::from_millis(16));
 
         // Always on Top
@@ -322,7 +916,11 @@ impl eframe::App for VoxMagicApp {
                         ui.label("Click ⚙, paste your key, and enable Always on Top.");
                         ui.add_space(5.0);
                         ui.label(egui::RichText::new("3. Magic").strong());
-                        ui.label("Hold F8, speak, and let go to paste refined text!");
+                        ui.label(match self.settings.trigger_mode {
+                            TriggerMode::PushToTalk => format!("Hold {}, speak, and let go to paste refined text!", self.main_hotkey_label()),
+                            TriggerMode::Toggle => format!("Tap {}, speak, and tap again to paste refined text!", self.main_hotkey_label()),
+                            TriggerMode::DoubleTapLock => format!("Hold {} while you speak, or double-tap it to dictate hands-free!", self.main_hotkey_label()),
+                        });
                         ui.add_space(10.0);
                         if ui.button("Start Flowing").clicked() { self.show_help = false; }
                     });
@@ -340,8 +938,354 @@ impl eframe::App for VoxMagicApp {
                             if resp.changed() { let _ = self.settings.save(); }
                         });
                         ui.add_space(10.0);
+                        ui.horizontal(|ui| {
+                            ui.label("Microphone:");
+                            let before = self.settings.input_device.clone();
+                            let selected = if self.settings.input_device.is_empty() { "System Default" } else { self.settings.input_device.as_str() };
+                            egui::ComboBox::from_id_salt("input_device")
+                                .selected_text(selected.to_string())
+                                .width(220.0)
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut self.settings.input_device, String::new(), "System Default");
+                                    for device in &self.input_devices {
+                                        let label = if device.is_default { format!("{} (default)", device.name) } else { device.name.clone() };
+                                        ui.selectable_value(&mut self.settings.input_device, device.name.clone(), label)
+                                            .on_hover_text(device.configs.join("\n"));
+                                    }
+                                });
+                            if self.settings.input_device != before { let _ = self.settings.save(); }
+                            if ui.small_button("↻").on_hover_text("Rescan devices").clicked() {
+                                self.input_devices = audio::list_input_devices().unwrap_or_default();
+                            }
+                        });
+                        if !self.settings.input_device.is_empty() && !self.input_devices.iter().any(|d| d.name == self.settings.input_device) {
+                            ui.label(egui::RichText::new("⚠ Saved microphone not connected, the default will be used").size(11.0).color(egui::Color32::from_rgb(250, 204, 21)));
+                        }
+                        ui.horizontal(|ui| {
+                            ui.label("Resampler:");
+                            let before = self.settings.resampler;
+                            egui::ComboBox::from_id_salt("resampler")
+                                .selected_text(self.settings.resampler.label())
+                                .show_ui(ui, |ui| {
+                                    for kind in ResamplerKind::ALL {
+                                        ui.selectable_value(&mut self.settings.resampler, kind, kind.label());
+                                    }
+                                });
+                            if self.settings.resampler != before { let _ = self.settings.save(); }
+                        });
+                        ui.add_space(10.0);
+                        ui.horizontal(|ui| {
+                            ui.label("Engine:");
+                            let before = self.settings.speech_backend;
+                            egui::ComboBox::from_id_salt("speech_backend")
+                                .selected_text(self.settings.speech_backend.label())
+                                .show_ui(ui, |ui| {
+                                    for kind in SpeechBackendKind::ALL {
+                                        ui.selectable_value(&mut self.settings.speech_backend, kind, kind.label());
+                                    }
+                                });
+                            if self.settings.speech_backend != before { let _ = self.settings.save(); }
+                        });
+                        if self.settings.speech_backend == SpeechBackendKind::Groq {
+                            ui.add_space(6.0);
+                            if validated_field(ui, "Endpoint:", &mut self.settings.transcription_url, validate_endpoint) { let _ = self.settings.save(); }
+                            if validated_field(ui, "Model:", &mut self.settings.whisper_model, validate_model) { let _ = self.settings.save(); }
+                        }
+                        if self.settings.speech_backend == SpeechBackendKind::OpenAiCompatible {
+                            ui.add_space(6.0);
+                            if validated_field(ui, "Server URL:", &mut self.settings.compatible_api_url, validate_endpoint) { let _ = self.settings.save(); }
+                            if validated_field(ui, "Model:", &mut self.settings.compatible_model, validate_model) { let _ = self.settings.save(); }
+                            ui.horizontal(|ui| {
+                                ui.label("Server Key:");
+                                let resp = ui.add(egui::TextEdit::singleline(&mut self.settings.compatible_api_key).password(true));
+                                if resp.changed() { let _ = self.settings.save(); }
+                            });
+                        }
+                        if self.settings.speech_backend != SpeechBackendKind::LocalWhisper {
+                            ui.horizontal(|ui| {
+                                ui.label("Upload Format:");
+                                let before = self.settings.upload_encoding;
+                                egui::ComboBox::from_id_salt("upload_encoding")
+                                    .selected_text(self.settings.upload_encoding.supported().label())
+                                    .show_ui(ui, |ui| {
+                                        for kind in AudioEncoding::ALL {
+                                            ui.selectable_value(&mut self.settings.upload_encoding, kind, kind.label());
+                                        }
+                                    });
+                                if self.settings.upload_encoding != before { let _ = self.settings.save(); }
+                            });
+                        }
+                        if self.settings.speech_backend == SpeechBackendKind::LocalWhisper {
+                            ui.add_space(6.0);
+                            ui.horizontal(|ui| {
+                                ui.label("Model File:");
+                                if ui.text_edit_singleline(&mut self.settings.local_model_path).changed() { let _ = self.settings.save(); }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("CPU Threads:");
+                                if ui.add(egui::DragValue::new(&mut self.settings.local_threads).range(1..=64)).changed() { let _ = self.settings.save(); }
+                            });
+                        }
+                        ui.add_space(10.0);
+                        ui.horizontal(|ui| {
+                            ui.label("Refinement:");
+                            let before = self.settings.refiner;
+                            egui::ComboBox::from_id_salt("refiner")
+                                .selected_text(self.settings.refiner.label())
+                                .show_ui(ui, |ui| {
+                                    for kind in RefinerKind::ALL {
+                                        ui.selectable_value(&mut self.settings.refiner, kind, kind.label());
+                                    }
+                                });
+                            if self.settings.refiner != before { let _ = self.settings.save(); }
+                        });
+                        if self.settings.refiner == RefinerKind::Chat {
+                            ui.add_space(6.0);
+                            if validated_field(ui, "Chat Base URL:", &mut self.settings.refine_base_url, validate_base_url) { let _ = self.settings.save(); }
+                            if validated_field(ui, "Chat Model:", &mut self.settings.refine_model, validate_model) { let _ = self.settings.save(); }
+                            ui.horizontal(|ui| {
+                                ui.label("Temperature:");
+                                if ui.add(egui::Slider::new(&mut self.settings.refine_temperature, 0.0..=1.0)).changed() { let _ = self.settings.save(); }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Chat Key:");
+                                let resp = ui.add(egui::TextEdit::singleline(&mut self.settings.refine_api_key).password(true).hint_text("Groq key if empty"));
+                                if resp.changed() { let _ = self.settings.save(); }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Mode:");
+                                let before = self.settings.refine_mode.clone();
+                                egui::ComboBox::from_id_salt("refine_mode")
+                                    .selected_text(self.settings.active_refine_mode().name)
+                                    .show_ui(ui, |ui| {
+                                        for mode in &self.settings.refine_modes {
+                                            let label = match mode.hotkey { Some(hotkey) => format!("{} ({})", mode.name, hotkey), None => mode.name.clone() };
+                                            ui.selectable_value(&mut self.settings.refine_mode, mode.name.clone(), label);
+                                        }
+                                    });
+                                if self.settings.refine_mode != before { let _ = self.settings.save(); }
+                            });
+                            egui::CollapsingHeader::new("Edit Modes").show(ui, |ui| {
+                                let mut changed = false;
+                                let mut remove = None;
+                                let mut capture = None;
+                                let target = self.binding_target;
+                                for (i, mode) in self.settings.refine_modes.iter_mut().enumerate() {
+                                    ui.push_id(i, |ui| {
+                                        ui.horizontal(|ui| {
+                                            let old_name = mode.name.clone();
+                                            if ui.add(egui::TextEdit::singleline(&mut mode.name).desired_width(120.0)).changed() {
+                                                if self.settings.refine_mode == old_name { self.settings.refine_mode = mode.name.clone(); }
+                                                changed = true;
+                                            }
+                                            let capturing = target == Some(BindingTarget::Mode(i));
+                                            if hotkey_button(ui, mode.hotkey, capturing).on_hover_text("Hotkey that records in this mode").clicked() {
+                                                capture = Some(BindingTarget::Mode(i));
+                                            }
+                                            if mode.hotkey.is_some() && ui.small_button("✕").on_hover_text("Unbind").clicked() {
+                                                mode.hotkey = None;
+                                                changed = true;
+                                            }
+                                            if ui.small_button("🗑").clicked() { remove = Some(i); }
+                                        });
+                                        if ui.add(egui::TextEdit::multiline(&mut mode.prompt).desired_rows(2)).changed() { changed = true; }
+                                    });
+                                    ui.add_space(4.0);
+                                }
+                                if let Some(i) = remove {
+                                    self.settings.refine_modes.remove(i);
+                                    self.cancel_capture();
+                                    changed = true;
+                                }
+                                if let Some(target) = capture {
+                                    self.start_capture(target);
+                                }
+                                ui.horizontal(|ui| {
+                                    if ui.small_button("+ Add Mode").clicked() {
+                                        self.settings.refine_modes.push(RefineMode {
+                                            name: format!("Custom {}", self.settings.refine_modes.len() + 1),
+                                            prompt: "Fix grammar and punctuation.".to_string(),
+                                            hotkey: None,
+                                        });
+                                        changed = true;
+                                    }
+                                    if ui.small_button("Restore built-in modes").clicked() {
+                                        for builtin in builtin_modes() {
+                                            match self.settings.refine_modes.iter_mut().find(|m| m.name == builtin.name) {
+                                                Some(mode) => mode.prompt = builtin.prompt,
+                                                None => self.settings.refine_modes.push(builtin),
+                                            }
+                                        }
+                                        changed = true;
+                                    }
+                                });
+                                if changed {
+                                    let _ = self.settings.save();
+                                    self.sync_bindings();
+                                }
+                            });
+                        }
+                        ui.add_space(6.0);
+                        if ui.small_button("Reset endpoints to defaults").clicked() {
+                            let defaults = AppSettings::default();
+                            self.settings.transcription_url = defaults.transcription_url;
+                            self.settings.whisper_model = defaults.whisper_model;
+                            self.settings.refine_base_url = defaults.refine_base_url;
+                            self.settings.refine_model = defaults.refine_model;
+                            let _ = self.settings.save();
+                        }
+                        ui.add_space(10.0);
+                        if ui.checkbox(&mut self.settings.vad_enabled, "Trim Silence (VAD)").changed() { let _ = self.settings.save(); }
+                        if self.settings.vad_enabled {
+                            ui.horizontal(|ui| {
+                                ui.label("Speech Threshold:");
+                                if ui.add(egui::Slider::new(&mut self.settings.vad_threshold_db, -70.0..=-20.0).suffix(" dB")).changed() { let _ = self.settings.save(); }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Max Pause:");
+                                if ui.add(egui::Slider::new(&mut self.settings.vad_max_gap_ms, 200..=3000).suffix(" ms")).changed() { let _ = self.settings.save(); }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Edge Padding:");
+                                if ui.add(egui::Slider::new(&mut self.settings.vad_padding_ms, 0..=500).suffix(" ms")).changed() { let _ = self.settings.save(); }
+                            });
+                        }
+                        if ui.checkbox(&mut self.settings.filter_hallucinations, "Filter Hallucinated Segments").changed() { let _ = self.settings.save(); }
+                        if self.settings.filter_hallucinations {
+                            ui.horizontal(|ui| {
+                                ui.label("Max No-Speech Prob:");
+                                if ui.add(egui::Slider::new(&mut self.settings.max_no_speech_prob, 0.0..=1.0)).changed() { let _ = self.settings.save(); }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Min Avg Logprob:");
+                                if ui.add(egui::Slider::new(&mut self.settings.min_avg_logprob, -3.0..=0.0)).changed() { let _ = self.settings.save(); }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Max Compression:");
+                                if ui.add(egui::Slider::new(&mut self.settings.max_compression_ratio, 1.0..=5.0)).changed() { let _ = self.settings.save(); }
+                            });
+                        }
+                        if ui.checkbox(&mut self.settings.streaming_mode, "Streaming Mode (transcribe while speaking)").changed() { let _ = self.settings.save(); }
+                        if self.settings.streaming_mode {
+                            ui.horizontal(|ui| {
+                                ui.label("Min Chunk:");
+                                if ui.add(egui::Slider::new(&mut self.settings.stream_min_chunk_secs, 3..=30).suffix(" s")).changed() { let _ = self.settings.save(); }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Split on Pause:");
+                                if ui.add(egui::Slider::new(&mut self.settings.stream_pause_ms, 200..=2000).suffix(" ms")).changed() { let _ = self.settings.save(); }
+                            });
+                        }
+                        ui.add_space(10.0);
+                        ui.horizontal(|ui| {
+                            ui.label("Retries:");
+                            if ui.add(egui::DragValue::new(&mut self.settings.max_retries).range(0..=8)).changed() { let _ = self.settings.save(); }
+                            ui.label("Max Wait:");
+                            if ui.add(egui::DragValue::new(&mut self.settings.retry_max_delay_ms).range(1_000..=60_000).speed(100).suffix(" ms")).changed() { let _ = self.settings.save(); }
+                        });
+                        ui.add_space(10.0);
+                        ui.horizontal_wrapped(|ui| {
+                            ui.label("Hotkeys:");
+                            let mut remove = None;
+                            let mut capture = None;
+                            let can_remove = self.settings.hotkeys.len() > 1;
+                            for (i, hotkey) in self.settings.hotkeys.iter().enumerate() {
+                                let capturing = self.binding_target == Some(BindingTarget::Main(i));
+                                if hotkey_button(ui, Some(*hotkey), capturing).on_hover_text("Click, then press the new combination").clicked() {
+                                    capture = Some(BindingTarget::Main(i));
+                                }
+                                if can_remove && ui.small_button("✕").clicked() { remove = Some(i); }
+                            }
+                            let adding = self.binding_target == Some(BindingTarget::NewMain);
+                            if ui.small_button(if adding { "Press keys..." } else { "+" }).on_hover_text("Add another hotkey").clicked() {
+                                capture = Some(BindingTarget::NewMain);
+                            }
+                            if self.binding_target.is_some() && ui.small_button("Cancel").clicked() {
+                                self.cancel_capture();
+                            }
+                            if let Some(i) = remove {
+                                self.settings.hotkeys.remove(i);
+                                self.cancel_capture();
+                                let _ = self.settings.save();
+                                self.sync_bindings();
+                            }
+                            if let Some(target) = capture {
+                                self.start_capture(target);
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Cancel Key:");
+                            let capturing = self.binding_target == Some(BindingTarget::Cancel);
+                            if hotkey_button(ui, self.settings.cancel_hotkey, capturing).on_hover_text("Discards the recording in progress without pasting").clicked() {
+                                self.start_capture(BindingTarget::Cancel);
+                            }
+                            if self.settings.cancel_hotkey.is_some() && ui.small_button("✕").on_hover_text("Unbind").clicked() {
+                                self.settings.cancel_hotkey = None;
+                                let _ = self.settings.save();
+                                self.sync_bindings();
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Trigger:");
+                            let before = self.settings.trigger_mode;
+                            egui::ComboBox::from_id_salt("trigger_mode")
+                                .selected_text(self.settings.trigger_mode.label())
+                                .show_ui(ui, |ui| {
+                                    for mode in TriggerMode::ALL {
+                                        ui.selectable_value(&mut self.settings.trigger_mode, mode, mode.label());
+                                    }
+                                });
+                            if self.settings.trigger_mode != before {
+                                let _ = self.settings.save();
+                                self.sync_bindings();
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Max Length:");
+                            let limit = egui::DragValue::new(&mut self.settings.max_recording_secs).range(0..=3600).suffix(" s");
+                            if ui.add(limit).on_hover_text("Recording stops and is transcribed after this long; 0 means no limit").changed() { let _ = self.settings.save(); }
+                        });
+                        ui.add_space(10.0);
                         if ui.checkbox(&mut self.settings.auto_paste, "Magic Auto-Paste").changed() { let _ = self.settings.save(); }
+                        ui.horizontal(|ui| {
+                            ui.label("Paste via:");
+                            let before = self.settings.injector;
+                            egui::ComboBox::from_id_salt("injector")
+                                .selected_text(self.settings.injector.label())
+                                .show_ui(ui, |ui| {
+                                    for kind in InjectorKind::ALL {
+                                        ui.selectable_value(&mut self.settings.injector, kind, kind.label());
+                                    }
+                                });
+                            if self.settings.injector != before {
+                                let _ = self.settings.save();
+                                self.reload_injector();
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Paste Keys:");
+                            let kind = self.injector_kind();
+                            let capturing = self.binding_target == Some(BindingTarget::Paste);
+                            let hint = format!("Sent through {} after the text is copied; terminals usually need Ctrl+Shift+V", kind.label());
+                            if hotkey_button(ui, Some(self.paste_shortcut()), capturing).on_hover_text(hint).clicked() {
+                                self.start_capture(BindingTarget::Paste);
+                            }
+                            if self.settings.paste_shortcuts.contains_key(&kind) && ui.small_button("✕").on_hover_text("Back to Ctrl+V").clicked() {
+                                self.settings.paste_shortcuts.remove(&kind);
+                                let _ = self.settings.save();
+                            }
+                        });
                         if ui.checkbox(&mut self.settings.always_on_top, "Always on Top").changed() { let _ = self.settings.save(); }
+                        ui.horizontal(|ui| {
+                            ui.label("Keep History:");
+                            let days = egui::DragValue::new(&mut self.settings.history_retention_days).range(0..=3650).suffix(" days");
+                            if ui.add(days).on_hover_text("0 keeps history forever").changed() {
+                                let _ = self.settings.save();
+                                if let Err(e) = self.history.apply_retention(self.settings.history_retention_days) {
+                                    println!("⚠️ Could not prune history: {}", e);
+                                }
+                            }
+                        });
                     });
                     ui.add_space(20.0);
                 }
@@ -383,31 +1327,351 @@ impl eframe::App for VoxMagicApp {
 
                 ui.vertical_centered(|ui| {
                     ui.label(egui::RichText::new(&self.status_message).size(16.0).strong().color(egui::Color32::from_rgb(200, 200, 210)));
+                    if matches!(self.state, AppState::Listening | AppState::Transcribing) {
+                        let hint = self.settings.cancel_hotkey.map(|hotkey| format!("Discard without pasting ({})", hotkey));
+                        if ui.small_button("✕ Cancel").on_hover_text(hint.unwrap_or_else(|| "Discard without pasting".to_string())).clicked() {
+                            self.cancel_recording();
+                        }
+                    }
+                    if !self.live_text.is_empty() {
+                        ui.add_space(6.0);
+                        ui.add(egui::Label::new(egui::RichText::new(&self.live_text).size(13.0).italics().color(egui::Color32::from_rgb(140, 140, 160))).wrap());
+                    }
                 });
 
                 ui.add_space(30.0);
 
                 // --- LOG ---
                 ui.label(egui::RichText::new("RECENT FLOWS").size(10.0).strong().color(egui::Color32::from_rgb(100, 100, 120)));
-                ui.add_space(10.0);
+                ui.add_space(6.0);
+                ui.horizontal(|ui| {
+                    ui.label("🔍");
+                    let search = egui::TextEdit::singleline(&mut self.history_query).hint_text("Search history");
+                    if ui.add(search).changed() {
+                        self.history_page = 0;
+                        self.expanded_entry = None;
+                    }
+                });
+                ui.add_space(6.0);
+
+                let matches = self.history.search(&self.history_query);
+                let page_count = matches.len().div_ceil(HISTORY_PAGE_SIZE).max(1);
+                self.history_page = self.history_page.min(page_count - 1);
+                let mut clicked_entry = None;
+                let mut paste_raw_entry = None;
                 egui::ScrollArea::vertical().max_height(180.0).show(ui, |ui| {
-                    for entry in &self.history {
-                        egui::Frame::none().fill(egui::Color32::from_rgb(12, 12, 16)).rounding(12.0).inner_margin(14.0).show(ui, |ui| {
-                            ui.add(egui::Label::new(egui::RichText::new(entry).size(14.0).color(egui::Color32::from_rgb(180, 180, 190))).wrap());
+                    for record in matches.iter().skip(self.history_page * HISTORY_PAGE_SIZE).take(HISTORY_PAGE_SIZE) {
+                        let expanded = self.expanded_entry == Some(record.id);
+                        let frame = egui::Frame::none().fill(egui::Color32::from_rgb(12, 12, 16)).rounding(12.0).inner_margin(14.0).show(ui, |ui| {
+                            ui.label(egui::RichText::new(history_summary(record)).size(10.0).color(egui::Color32::from_rgb(100, 100, 120)));
+                            if record.delayed {
+                                ui.label(egui::RichText::new("⏳ Delayed - not pasted").size(10.0).color(egui::Color32::from_rgb(250, 204, 21)));
+                            }
+                            ui.add(egui::Label::new(egui::RichText::new(&record.refined_text).size(14.0).color(egui::Color32::from_rgb(180, 180, 190))).wrap());
+                            if expanded {
+                                if record.raw_text != record.refined_text {
+                                    if self.diff_layout.as_ref().map(|(id, _)| *id) != Some(record.id) {
+                                        self.diff_layout = Some((record.id, word_diff_layout(&record.raw_text, &record.refined_text)));
+                                    }
+                                    if let Some((_, job)) = &self.diff_layout {
+                                        show_word_diff(ui, job.clone());
+                                    }
+                                    if ui.button("↩ Paste raw instead").on_hover_text("Use the transcription without refinement").clicked() {
+                                        paste_raw_entry = Some(record.id);
+                                    }
+                                }
+                                show_segment_details(ui, &record.segments);
+                            }
                         });
+                        if frame.response.interact(egui::Sense::click()).on_hover_text("Click for segment details").clicked() {
+                            clicked_entry = Some(record.id);
+                        }
                         ui.add_space(10.0);
                     }
+                    if matches.is_empty() && !self.history_query.trim().is_empty() {
+                        ui.label(egui::RichText::new("No matches").size(12.0).color(egui::Color32::from_rgb(100, 100, 120)));
+                    }
                 });
+                if let Some(id) = paste_raw_entry {
+                    self.paste_raw(id);
+                } else if let Some(id) = clicked_entry {
+                    self.expanded_entry = if self.expanded_entry == Some(id) { None } else { Some(id) };
+                }
+                if page_count > 1 {
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(self.history_page > 0, egui::Button::new("◀")).clicked() {
+                            self.history_page -= 1;
+                        }
+                        ui.label(format!("Page {} / {}", self.history_page + 1, page_count));
+                        if ui.add_enabled(self.history_page + 1 < page_count, egui::Button::new("▶")).clicked() {
+                            self.history_page += 1;
+                        }
+                    });
+                }
 
                 ui.add_space(15.0);
                 ui.separator();
                 ui.add_space(10.0);
                 ui.horizontal(|ui| {
-                    if ui.button("🗑 Clear").clicked() { self.history.clear(); }
+                    if ui.button("🗑 Clear").clicked() {
+                        if let Err(e) = self.history.clear() {
+                            println!("⚠️ Could not clear history: {}", e);
+                        }
+                        self.history_page = 0;
+                        self.expanded_entry = None;
+                    }
+                    if self.queued_count > 0 {
+                        let label = format!("⟳ Retry Offline ({})", self.queued_count);
+                        if ui.add_enabled(!self.queue_flushing, egui::Button::new(label)).on_hover_text("Send recordings saved while offline").clicked() {
+                            self.flush_queue();
+                        }
+                    }
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        ui.label(egui::RichText::new("HOLD F8 TO COMMENCE").size(10.0).strong().color(egui::Color32::from_rgb(80, 80, 100)));
+                        ui.label(egui::RichText::new(format!("{} {} TO COMMENCE", if self.settings.trigger_mode == TriggerMode::Toggle { "TAP" } else { "HOLD" }, self.main_hotkey_label().to_uppercase())).size(10.0).strong().color(egui::Color32::from_rgb(80, 80, 100)));
                     });
                 });
             });
     }
 }
+
+/// Transcribe streamed chunks in order, publishing partial text, then refine the whole result once.
+/// After a transient failure the rest of the recording is collected and put in the offline queue,
+/// while the text transcribed up to then is still delivered.
+fn run_streaming_worker(
+    chunk_rx: Receiver<AudioChunk>,
+    transcriber: Transcriber,
+    options: ProcessingOptions,
+    context: RecordingContext,
+    tx: JobSender,
+) {
+    let mut combined = TranscriptionResponse::empty();
+    let mut failure = None;
+    // Audio from the first chunk that could not be sent onwards, with the error that stopped it
+    let mut unsent: Option<(AudioChunk, TranscriptionError)> = None;
+
+    // The loop ends when the drain thread drops the sender after flushing the tail
+    for chunk in chunk_rx {
+        if failure.is_some() || tx.is_cancelled() {
+            continue;
+        }
+        if let Some((rest, _)) = &mut unsent {
+            rest.samples.extend(chunk.samples);
+            continue;
+        }
+        let offset_secs = chunk.start_ms as f64 / 1000.0;
+        let original = AudioChunk { samples: chunk.samples.clone(), ..chunk };
+        let audio_data = match audio::encode_chunk(chunk, &options) {
+            Ok(data) => data,
+            Err(AudioError::TooShort(_)) | Err(AudioError::Silent(_)) => continue,
+            Err(e) => {
+                failure = Some(TranscriptionError::RequestError(e.to_string()));
+                continue;
+            }
+        };
+
+        match transcriber.transcribe_raw(audio_data) {
+            Ok(result) => {
+                combined.append(result, offset_secs);
+                tx.send(AppMessage::PartialTranscript(combined.text.clone()));
+            }
+            Err(e) if e.is_transient() => unsent = Some((original, e)),
+            Err(e) => failure = Some(e),
+        }
+    }
+
+    if tx.is_cancelled() {
+        return;
+    }
+    if let Some(error) = failure {
+        tx.send(AppMessage::TranscriptionError(error));
+        return;
+    }
+
+    let queued = unsent.and_then(|(rest, error)| {
+        let context = RecordingContext {
+            duration_ms: rest.samples.len() as u64 * 1000 / rest.sample_rate.max(1) as u64,
+            ..context
+        };
+        // A remainder that is only silence is not worth keeping
+        let audio_data = audio::encode_chunk(rest, &options).ok()?;
+        Some(match queue::enqueue(&audio_data, &error, context) {
+            Ok(_) => AppMessage::Queued(error),
+            Err(io_error) => {
+                println!("⚠️ Could not queue recording: {}", io_error);
+                AppMessage::TranscriptionError(error)
+            }
+        })
+    });
+
+    match queued {
+        Some(message) if combined.text.trim().is_empty() => tx.send(message),
+        Some(message) => {
+            tx.send(AppMessage::TranscriptionComplete(transcriber.refine(combined)));
+            tx.send(message);
+        }
+        None => tx.send(AppMessage::TranscriptionComplete(transcriber.refine(combined))),
+    }
+}
+
+/// One-line description of when and where a history record was made
+fn history_summary(record: &HistoryRecord) -> String {
+    let now = history::now_ms() / 1000;
+    let age = now.saturating_sub(record.timestamp());
+    let when = match age {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{} min ago", age / 60),
+        3600..=86_399 => format!("{} h ago", age / 3600),
+        _ => format!("{} days ago", age / 86_400),
+    };
+
+    let mut parts = vec![when, format!("{:.1}s", record.context.duration_ms as f64 / 1000.0)];
+    if let Some(app) = record.context.target_app.as_deref().filter(|a| !a.is_empty()) {
+        parts.push(app.to_string());
+    }
+    if !record.context.model.is_empty() {
+        parts.push(record.context.model.clone());
+    }
+    parts.join(" · ")
+}
+
+fn release_grace(mode: TriggerMode) -> u64 {
+    match mode {
+        TriggerMode::DoubleTapLock => TAP_RELEASE_GRACE_MS,
+        TriggerMode::PushToTalk | TriggerMode::Toggle => RELEASE_GRACE_MS,
+    }
+}
+
+/// Button showing a binding; reads "Press keys..." while the hotkey thread is capturing for it
+fn hotkey_button(ui: &mut egui::Ui, hotkey: Option<Hotkey>, capturing: bool) -> egui::Response {
+    let text = match (capturing, hotkey) {
+        (true, _) => "Press keys...".to_string(),
+        (false, Some(hotkey)) => hotkey.to_string(),
+        (false, None) => "Set hotkey".to_string(),
+    };
+    ui.button(text)
+}
+
+/// Handle of the window that currently has keyboard focus
+#[cfg(windows)]
+fn foreground_window() -> Option<isize> {
+    let window = unsafe { GetForegroundWindow() };
+    (!window.is_null()).then_some(window as isize)
+}
+
+#[cfg(windows)]
+fn window_title(window: isize) -> Option<String> {
+    let mut buffer = [0u16; 256];
+    let len = unsafe { GetWindowTextW(window as _, buffer.as_mut_ptr(), buffer.len() as i32) };
+    (len > 0).then(|| String::from_utf16_lossy(&buffer[..len as usize]))
+}
+
+/// Bring a window back to the front; false if that is not possible here
+#[cfg(windows)]
+fn focus_window(window: isize) -> bool {
+    unsafe { SetForegroundWindow(window as _) != 0 }
+}
+
+// Other platforms do not track the target window; "paste raw" falls back to the clipboard
+#[cfg(not(windows))]
+fn foreground_window() -> Option<isize> {
+    None
+}
+
+#[cfg(not(windows))]
+fn window_title(_window: isize) -> Option<String> {
+    None
+}
+
+#[cfg(not(windows))]
+fn focus_window(_window: isize) -> bool {
+    false
+}
+
+/// Turn an error into a short status line that tells the user what to do about it
+fn error_guidance(error: &TranscriptionError) -> String {
+    match error {
+        TranscriptionError::Unauthorized(_) => "API key rejected - check it in Settings".to_string(),
+        TranscriptionError::RateLimited { retry_after: Some(wait) } => {
+            format!("Rate limited - try again in {}s", wait.as_secs().max(1))
+        }
+        TranscriptionError::RateLimited { retry_after: None } => "Rate limited - wait a moment and try again".to_string(),
+        TranscriptionError::Timeout => "Request timed out - check your connection or raise Retries".to_string(),
+        TranscriptionError::Network(_) => "Can't reach the server - check your internet connection".to_string(),
+        TranscriptionError::PayloadTooLarge => "Recording too large - pick a compressed Upload Format".to_string(),
+        TranscriptionError::ServerError(status) => format!("Server error ({}) - try again shortly", status.as_u16()),
+        TranscriptionError::Refinement(inner) => format!("refinement failed: {}", error_guidance(inner)),
+        other => format!("Error: {}", other),
+    }
+}
+
+/// Single-line text setting with an inline error shown while the value is invalid
+fn validated_field(ui: &mut egui::Ui, label: &str, value: &mut String, validate: fn(&str) -> Result<(), String>) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        ui.label(label);
+        changed = ui.text_edit_singleline(value).changed();
+    });
+    if let Err(e) = validate(value) {
+        ui.label(egui::RichText::new(format!("⚠ {}", e)).size(11.0).color(egui::Color32::from_rgb(248, 113, 113)));
+    }
+    changed
+}
+
+/// Raw transcription against the refined text: removed words struck through in red, added words in green
+fn word_diff_layout(raw: &str, refined: &str) -> egui::text::LayoutJob {
+    let font = egui::FontId::proportional(12.0);
+    let removed = egui::Color32::from_rgb(248, 113, 113);
+    let mut job = egui::text::LayoutJob::default();
+    for part in diff::word_diff(raw, refined) {
+        let (word, format) = match part {
+            DiffPart::Same(word) => (word, egui::TextFormat::simple(font.clone(), egui::Color32::from_rgb(160, 160, 170))),
+            DiffPart::Removed(word) => (word, egui::TextFormat {
+                strikethrough: egui::Stroke::new(1.0, removed),
+                ..egui::TextFormat::simple(font.clone(), removed)
+            }),
+            DiffPart::Added(word) => (word, egui::TextFormat::simple(font.clone(), egui::Color32::from_rgb(134, 239, 172))),
+        };
+        job.append(word, 0.0, format);
+        job.append(" ", 0.0, egui::TextFormat::simple(font.clone(), egui::Color32::TRANSPARENT));
+    }
+    job
+}
+
+fn show_word_diff(ui: &mut egui::Ui, mut job: egui::text::LayoutJob) {
+    ui.add_space(8.0);
+    ui.separator();
+    ui.label(egui::RichText::new("Raw → refined").size(10.0).color(egui::Color32::from_rgb(100, 100, 120)));
+    job.wrap.max_width = ui.available_width();
+    ui.label(job);
+    ui.add_space(4.0);
+}
+
+/// Segment breakdown for a history entry, with hallucination-filtered segments struck through
+fn show_segment_details(ui: &mut egui::Ui, segments: &[Segment]) {
+    ui.add_space(8.0);
+    ui.separator();
+    if segments.is_empty() {
+        ui.label(egui::RichText::new("No segment data from this engine").size(11.0).color(egui::Color32::from_rgb(100, 100, 120)));
+        return;
+    }
+
+    for segment in segments {
+        let header = format!(
+            "{:.1}s-{:.1}s  no-speech {:.2}  logprob {:.2}  compression {:.2}",
+            segment.start, segment.end, segment.no_speech_prob, segment.avg_logprob, segment.compression_ratio
+        );
+        ui.label(egui::RichText::new(header).size(10.0).color(egui::Color32::from_rgb(100, 100, 120)));
+
+        let text = egui::RichText::new(segment.text.trim()).size(12.0);
+        match segment.dropped {
+            Some(reason) => {
+                ui.add(egui::Label::new(text.strikethrough().color(egui::Color32::from_rgb(248, 113, 113))).wrap())
+                    .on_hover_text(format!("Filtered: {}", reason.label()));
+            }
+            None => {
+                ui.add(egui::Label::new(text.color(egui::Color32::from_rgb(134, 239, 172))).wrap());
+            }
+        }
+        ui.add_space(4.0);
+    }
+}
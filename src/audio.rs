@@ -1,14 +1,21 @@
 //! Audio recording module using cpal for low-latency capture
 
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::SampleFormat;
-use hound::{WavSpec, WavWriter};
+use cpal::{FromSample, Sample, SampleFormat, SizedSample};
+use hound::{WavReader, WavSpec, WavWriter};
+use rtrb::{Consumer, Producer, RingBuffer};
 use std::io::Cursor;
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::mpsc::Sender;
 use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
 use thiserror::Error;
 
-use crate::config::{BITS_PER_SAMPLE, CHANNELS};
+use crate::config::{BITS_PER_SAMPLE, CHANNELS, MIN_RECORDING_MS, MIN_RMS_DB, RING_BUFFER_SECONDS};
+use crate::settings::{AudioEncoding, ResamplerKind};
+use crate::{flac, opus, resample};
+use crate::vad::{self, ChunkerConfig, PauseChunker, VadConfig};
 
 #[derive(Error, Debug)]
 pub enum AudioError {
@@ -24,17 +31,66 @@ pub enum AudioError {
     AlreadyRecording,
     #[error("Not currently recording")]
     NotRecording,
+    #[error("Recording too short ({0} ms)")]
+    TooShort(u64),
+    #[error("Recording is silent ({0:.0} dBFS)")]
+    Silent(f32),
 }
 
-/// Thread-safe sample buffer
+/// An input device and the stream configurations it supports
+#[derive(Clone, Debug)]
+pub struct InputDeviceInfo {
+    pub name: String,
+    pub is_default: bool,
+    pub configs: Vec<String>,
+}
+
+/// A running capture stream
+pub struct StartedRecording {
+    pub stream: cpal::Stream,
+    /// Name of the requested device when it was missing and the default was used instead
+    pub missing_device: Option<String>,
+}
+
+/// Post-capture processing applied by `stop_recording`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ProcessingOptions {
+    /// Trim silence before encoding when set
+    pub vad: Option<VadConfig>,
+    pub resampler: ResamplerKind,
+}
+
+/// A piece of a live recording cut at a pause, emitted while the user is still speaking
+pub struct AudioChunk {
+    pub samples: Vec<i16>,
+    pub sample_rate: u32,
+    /// Position of the chunk within the whole recording
+    pub start_ms: u64,
+}
+
+/// Where streaming mode delivers chunks; the sender is dropped once the final tail is sent
+pub struct ChunkSink {
+    pub config: ChunkerConfig,
+    pub tx: Sender<AudioChunk>,
+}
+
+/// Accumulated recording, filled by the drain thread (never touched by the audio callback)
 pub type SampleBuffer = Arc<Mutex<Vec<i16>>>;
 
+/// How often the drain thread empties the ring buffer
+const DRAIN_INTERVAL_MS: u64 = 10;
+
 /// Recording state that can be shared across threads
 pub struct RecordingState {
     pub is_recording: Arc<AtomicBool>,
     pub samples: SampleBuffer,
     pub actual_sample_rate: Arc<AtomicU32>,
     pub actual_channels: Arc<AtomicU32>,
+    /// Samples the callback had to discard because the ring buffer was full
+    pub dropped_samples: Arc<AtomicU64>,
+    /// Raised when the recording is thrown away, so the drain thread stops without emitting its last chunk
+    discarded: Arc<AtomicBool>,
+    drain_thread: Arc<Mutex<Option<JoinHandle<()>>>>,
 }
 
 impl RecordingState {
@@ -44,6 +100,17 @@ impl RecordingState {
             samples: Arc::new(Mutex::new(Vec::new())),
             actual_sample_rate: Arc::new(AtomicU32::new(16000)),
             actual_channels: Arc::new(AtomicU32::new(1)),
+            dropped_samples: Arc::new(AtomicU64::new(0)),
+            discarded: Arc::new(AtomicBool::new(false)),
+            drain_thread: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Wait for the drain thread to move the last samples out of the ring buffer
+    fn join_drain_thread(&self) {
+        let handle = self.drain_thread.lock().ok().and_then(|mut guard| guard.take());
+        if let Some(handle) = handle {
+            let _ = handle.join();
         }
     }
 }
@@ -61,12 +128,76 @@ impl Clone for RecordingState {
             samples: self.samples.clone(),
             actual_sample_rate: self.actual_sample_rate.clone(),
             actual_channels: self.actual_channels.clone(),
+            dropped_samples: self.dropped_samples.clone(),
+            discarded: self.discarded.clone(),
+            drain_thread: self.drain_thread.clone(),
+        }
+    }
+}
+
+/// List every input device on the default host with its supported configs
+pub fn list_input_devices() -> Result<Vec<InputDeviceInfo>, AudioError> {
+    let host = cpal::default_host();
+    let default_name = host.default_input_device().and_then(|d| d.name().ok());
+    let devices = host
+        .input_devices()
+        .map_err(|e| AudioError::ConfigError(e.to_string()))?;
+
+    Ok(devices
+        .filter_map(|device| {
+            let name = device.name().ok()?;
+            let configs = device
+                .supported_input_configs()
+                .map(|configs| {
+                    configs
+                        .map(|c| format!(
+                            "{} ch, {}-{} Hz, {:?}",
+                            c.channels(),
+                            c.min_sample_rate().0,
+                            c.max_sample_rate().0,
+                            c.sample_format()
+                        ))
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            Some(InputDeviceInfo {
+                is_default: default_name.as_deref() == Some(name.as_str()),
+                name,
+                configs,
+            })
+        })
+        .collect())
+}
+
+/// Find the named input device, falling back to the system default when it is missing.
+/// An empty name means "system default".
+fn select_input_device(host: &cpal::Host, name: &str) -> Result<(cpal::Device, Option<String>), AudioError> {
+    if !name.is_empty() {
+        let found = host
+            .input_devices()
+            .ok()
+            .and_then(|mut devices| devices.find(|d| d.name().map(|n| n == name).unwrap_or(false)));
+        if let Some(device) = found {
+            return Ok((device, None));
         }
+        println!("⚠️ Input device \"{}\" not found, using default", name);
     }
+
+    let device = host
+        .default_input_device()
+        .ok_or(AudioError::NoInputDevice)?;
+    let missing = (!name.is_empty()).then(|| name.to_string());
+    Ok((device, missing))
 }
 
-/// Start recording audio from the default input device
-pub fn start_recording(state: &RecordingState) -> Result<cpal::Stream, AudioError> {
+/// Start recording audio from the named input device (or the default one).
+/// With a `chunk_sink`, the recording is also cut at pauses and streamed out as it goes.
+pub fn start_recording(
+    state: &RecordingState,
+    device_name: &str,
+    chunk_sink: Option<ChunkSink>,
+) -> Result<StartedRecording, AudioError> {
     if state.is_recording.load(Ordering::Relaxed) {
         return Err(AudioError::AlreadyRecording);
     }
@@ -76,11 +207,9 @@ pub fn start_recording(state: &RecordingState) -> Result<cpal::Stream, AudioErro
         samples.clear();
     }
 
-    // Get the default host and input device
+    // Get the default host and the requested input device
     let host = cpal::default_host();
-    let device = host
-        .default_input_device()
-        .ok_or(AudioError::NoInputDevice)?;
+    let (device, missing_device) = select_input_device(&host, device_name)?;
 
     println!("🎤 Using input device: {}", device.name().unwrap_or_default());
 
@@ -97,46 +226,80 @@ pub fn start_recording(state: &RecordingState) -> Result<cpal::Stream, AudioErro
     state.actual_sample_rate.store(actual_rate, Ordering::Relaxed);
     state.actual_channels.store(actual_channels, Ordering::Relaxed);
 
+    // Allocate the ring up front so the callback never allocates
+    let (producer, consumer) = RingBuffer::<i16>::new((actual_rate * RING_BUFFER_SECONDS) as usize);
     let is_recording = state.is_recording.clone();
-    let samples = state.samples.clone();
-
-    // Build the input stream based on sample format
-    let stream = match config.sample_format() {
-        SampleFormat::I16 => build_stream_i16(&device, &config.into(), samples, is_recording.clone(), actual_channels)?,
-        SampleFormat::F32 => build_stream_f32(&device, &config.into(), samples, is_recording.clone(), actual_channels)?,
+    let dropped = state.dropped_samples.clone();
+    dropped.store(0, Ordering::Relaxed);
+    state.discarded.store(false, Ordering::Relaxed);
+
+    // Build the input stream based on sample format. cpal 0.15 has no 24-bit `SampleFormat`,
+    // so 24-bit devices are only usable through one of the configs listed below.
+    let sample_format = config.sample_format();
+    let config: cpal::StreamConfig = config.into();
+    let stream = match sample_format {
+        SampleFormat::I8 => build_stream::<i8>(&device, &config, producer, is_recording, dropped, actual_channels)?,
+        SampleFormat::I16 => build_stream::<i16>(&device, &config, producer, is_recording, dropped, actual_channels)?,
+        SampleFormat::I32 => build_stream::<i32>(&device, &config, producer, is_recording, dropped, actual_channels)?,
+        SampleFormat::I64 => build_stream::<i64>(&device, &config, producer, is_recording, dropped, actual_channels)?,
+        SampleFormat::U8 => build_stream::<u8>(&device, &config, producer, is_recording, dropped, actual_channels)?,
+        SampleFormat::U16 => build_stream::<u16>(&device, &config, producer, is_recording, dropped, actual_channels)?,
+        SampleFormat::U32 => build_stream::<u32>(&device, &config, producer, is_recording, dropped, actual_channels)?,
+        SampleFormat::U64 => build_stream::<u64>(&device, &config, producer, is_recording, dropped, actual_channels)?,
+        SampleFormat::F32 => build_stream::<f32>(&device, &config, producer, is_recording, dropped, actual_channels)?,
+        SampleFormat::F64 => build_stream::<f64>(&device, &config, producer, is_recording, dropped, actual_channels)?,
         format => return Err(AudioError::ConfigError(format!("Unsupported format: {:?}", format))),
     };
 
-    stream.play().map_err(|e| AudioError::StreamError(e.to_string()))?;
     state.is_recording.store(true, Ordering::Relaxed);
+    if let Err(e) = stream.play() {
+        state.is_recording.store(false, Ordering::Relaxed);
+        return Err(AudioError::StreamError(e.to_string()));
+    }
+
+    let handle = spawn_drain_thread(
+        consumer,
+        state.samples.clone(),
+        state.is_recording.clone(),
+        state.discarded.clone(),
+        chunk_sink,
+        actual_rate,
+    );
+    if let Ok(mut guard) = state.drain_thread.lock() {
+        *guard = Some(handle);
+    }
 
     println!("🔴 Recording started...");
-    Ok(stream)
+    Ok(StartedRecording { stream, missing_device })
 }
 
-fn build_stream_i16(
+/// Build a stream whose callback only mixes to mono and pushes into the lock-free ring
+fn build_stream<T>(
     device: &cpal::Device,
     config: &cpal::StreamConfig,
-    samples: SampleBuffer,
+    mut producer: Producer<i16>,
     is_recording: Arc<AtomicBool>,
+    dropped: Arc<AtomicU64>,
     channels: u32,
-) -> Result<cpal::Stream, AudioError> {
+) -> Result<cpal::Stream, AudioError>
+where
+    T: SizedSample,
+    i16: FromSample<T>,
+{
     let err_fn = |err| eprintln!("Stream error: {}", err);
 
     let stream = device
         .build_input_stream(
             config,
-            move |data: &[i16], _: &cpal::InputCallbackInfo| {
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
                 if !is_recording.load(Ordering::Relaxed) {
                     return;
                 }
 
-                if let Ok(mut samples_guard) = samples.lock() {
-                    // Mix down to mono
-                    for frame in data.chunks_exact(channels as usize) {
-                        let sum: i32 = frame.iter().map(|&s| s as i32).sum();
-                        let mono = (sum / channels as i32) as i16;
-                        samples_guard.push(mono);
+                // Mix down to mono
+                for frame in data.chunks_exact(channels as usize) {
+                    if producer.push(mix_to_mono(frame)).is_err() {
+                        dropped.fetch_add(1, Ordering::Relaxed);
                     }
                 }
             },
@@ -148,74 +311,181 @@ fn build_stream_i16(
     Ok(stream)
 }
 
-fn build_stream_f32(
-    device: &cpal::Device,
-    config: &cpal::StreamConfig,
+/// Move samples from the ring buffer into the shared buffer until recording stops,
+/// emitting pause-delimited chunks to `chunk_sink` along the way. A discarded recording drops the sink unflushed.
+fn spawn_drain_thread(
+    mut consumer: Consumer<i16>,
     samples: SampleBuffer,
     is_recording: Arc<AtomicBool>,
-    channels: u32,
-) -> Result<cpal::Stream, AudioError> {
-    let err_fn = |err| eprintln!("Stream error: {}", err);
+    discarded: Arc<AtomicBool>,
+    chunk_sink: Option<ChunkSink>,
+    sample_rate: u32,
+) -> JoinHandle<()> {
+    let mut chunker = chunk_sink.as_ref().map(|sink| PauseChunker::new(sink.config, sample_rate));
+    let to_ms = move |index: usize| index as u64 * 1000 / sample_rate.max(1) as u64;
+
+    thread::spawn(move || loop {
+        // Read the flag before draining so the final pass picks up everything written before stop
+        let finished = !is_recording.load(Ordering::Acquire) || consumer.is_abandoned();
+        if discarded.load(Ordering::Acquire) {
+            break;
+        }
 
-    let stream = device
-        .build_input_stream(
-            config,
-            move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                if !is_recording.load(Ordering::Relaxed) {
-                    return;
+        let available = consumer.slots();
+        if available > 0 {
+            if let Ok(chunk) = consumer.read_chunk(available) {
+                let (first, second) = chunk.as_slices();
+                if let Ok(mut guard) = samples.lock() {
+                    guard.extend_from_slice(first);
+                    guard.extend_from_slice(second);
                 }
+                chunk.commit_all();
+            }
+        }
 
-                if let Ok(mut samples_guard) = samples.lock() {
-                    // Mix down to mono and convert to i16
-                    for frame in data.chunks_exact(channels as usize) {
-                        let sum: f32 = frame.iter().sum();
-                        let avg = sum / channels as f32;
-                        let mono_i16 = (avg * 32767.0).clamp(-32768.0, 32767.0) as i16;
-                        samples_guard.push(mono_i16);
-                    }
+        if let (Some(sink), Some(chunker)) = (&chunk_sink, chunker.as_mut()) {
+            if let Ok(guard) = samples.lock() {
+                let mut start = chunker.chunk_start();
+                while let Some(cut) = chunker.next_cut(&guard) {
+                    let _ = sink.tx.send(AudioChunk { samples: guard[start..cut].to_vec(), sample_rate, start_ms: to_ms(start) });
+                    start = cut;
                 }
-            },
-            err_fn,
-            None,
-        )
-        .map_err(|e| AudioError::StreamError(e.to_string()))?;
+                if finished && start < guard.len() {
+                    let _ = sink.tx.send(AudioChunk { samples: guard[start..].to_vec(), sample_rate, start_ms: to_ms(start) });
+                }
+            }
+        }
 
-    Ok(stream)
+        if finished {
+            break;
+        }
+        thread::sleep(Duration::from_millis(DRAIN_INTERVAL_MS));
+    })
 }
 
-/// Stop recording and return WAV data
-pub fn stop_recording(state: &RecordingState) -> Result<Vec<u8>, AudioError> {
+/// Convert one interleaved frame of any cpal sample type to a mono i16 sample
+pub fn mix_to_mono<T>(frame: &[T]) -> i16
+where
+    T: Sample,
+    i16: FromSample<T>,
+{
+    if frame.is_empty() {
+        return 0;
+    }
+    let sum: i32 = frame.iter().map(|&s| i16::from_sample(s) as i32).sum();
+    (sum / frame.len() as i32) as i16
+}
+
+/// Stop the stream's data flow and wait for the drain thread to finish
+fn halt_capture(state: &RecordingState) -> Result<(), AudioError> {
     if !state.is_recording.load(Ordering::Relaxed) {
         return Err(AudioError::NotRecording);
     }
 
-    state.is_recording.store(false, Ordering::Relaxed);
+    state.is_recording.store(false, Ordering::Release);
+    state.join_drain_thread();
     println!("⏹️ Recording stopped");
 
+    let dropped = state.dropped_samples.load(Ordering::Relaxed);
+    if dropped > 0 {
+        println!("⚠️ Ring buffer overrun, {} samples dropped", dropped);
+    }
+    Ok(())
+}
+
+/// Stop recording and return WAV data processed according to `options`
+pub fn stop_recording(state: &RecordingState, options: &ProcessingOptions) -> Result<Vec<u8>, AudioError> {
+    halt_capture(state)?;
+
     // Get samples and encode to WAV
     let samples = state.samples.lock()
         .map_err(|_| AudioError::EncodingError("Failed to lock samples".to_string()))?
         .clone();
 
+    // Get the actual sample rate that was used
+    let sample_rate = state.actual_sample_rate.load(Ordering::Relaxed);
+    process_and_encode(samples, sample_rate, options)
+}
+
+/// Stop recording and throw the captured audio away
+pub fn discard_recording(state: &RecordingState) -> Result<(), AudioError> {
+    if state.is_recording.load(Ordering::Relaxed) {
+        state.discarded.store(true, Ordering::Release);
+    }
+    halt_capture(state)?;
+    if let Ok(mut samples) = state.samples.lock() {
+        samples.clear();
+    }
+    println!("🗑️ Recording discarded");
+    Ok(())
+}
+
+/// Stop a streaming recording; the drain thread flushes the tail to the chunk sink before this returns.
+/// The whole recording goes through the same too-short and silence gate as `stop_recording`.
+pub fn stop_streaming(state: &RecordingState) -> Result<(), AudioError> {
+    halt_capture(state)?;
+    let samples = state.samples.lock()
+        .map_err(|_| AudioError::EncodingError("Failed to lock samples".to_string()))?;
+    check_speech(&samples, state.actual_sample_rate.load(Ordering::Relaxed)).map(|_| ())
+}
+
+/// Gate, trim and encode one streamed chunk the same way as a whole recording
+pub fn encode_chunk(chunk: AudioChunk, options: &ProcessingOptions) -> Result<Vec<u8>, AudioError> {
+    process_and_encode(chunk.samples, chunk.sample_rate, options)
+}
+
+/// Reject accidental taps and room noise before spending a network round trip; returns the duration in ms
+fn check_speech(samples: &[i16], sample_rate: u32) -> Result<u64, AudioError> {
     if samples.is_empty() {
         return Err(AudioError::EncodingError("No audio captured".to_string()));
     }
+    let duration_ms = samples.len() as u64 * 1000 / sample_rate.max(1) as u64;
+    if duration_ms < MIN_RECORDING_MS {
+        return Err(AudioError::TooShort(duration_ms));
+    }
+    let level_db = vad::frame_db(samples);
+    if level_db < MIN_RMS_DB {
+        return Err(AudioError::Silent(level_db));
+    }
+    Ok(duration_ms)
+}
 
-    // Get the actual sample rate that was used
-    let sample_rate = state.actual_sample_rate.load(Ordering::Relaxed);
+fn process_and_encode(samples: Vec<i16>, sample_rate: u32, options: &ProcessingOptions) -> Result<Vec<u8>, AudioError> {
+    let duration_ms = check_speech(&samples, sample_rate)?;
+
+    let samples = match &options.vad {
+        Some(config) => match vad::trim_silence(&samples, sample_rate, config) {
+            Some(trimmed) => {
+                println!(
+                    "✂️ VAD trimmed {} ms to {} ms",
+                    duration_ms,
+                    trimmed.len() as u64 * 1000 / sample_rate as u64
+                );
+                trimmed
+            }
+            None => {
+                println!("✂️ VAD found no speech, keeping untrimmed audio");
+                samples
+            }
+        },
+        None => samples,
+    };
     println!("📦 Encoding {} samples to WAV at {} Hz...", samples.len(), sample_rate);
 
     // Encode to WAV in memory with correct sample rate
-    encode_wav(&samples, sample_rate)
+    encode_wav(&samples, sample_rate, options.resampler)
 }
 
-fn encode_wav(samples: &[i16], original_sample_rate: u32) -> Result<Vec<u8>, AudioError> {
+fn encode_wav(samples: &[i16], original_sample_rate: u32, resampler: ResamplerKind) -> Result<Vec<u8>, AudioError> {
     // Target sample rate for Whisper API is 16000Hz
     let target_sample_rate = 16000;
 
     let processed_samples = if original_sample_rate != target_sample_rate {
-        println!("🔄 Downsampling from {}Hz to {}Hz...", original_sample_rate, target_sample_rate);
-        downsample(samples, original_sample_rate, target_sample_rate)
+        println!("🔄 Resampling from {}Hz to {}Hz ({})...", original_sample_rate, target_sample_rate, resampler.label());
+        match resampler {
+            ResamplerKind::Fast => resample::linear(samples, original_sample_rate, target_sample_rate),
+            ResamplerKind::HighQuality => resample::sinc(samples, original_sample_rate, target_sample_rate),
+        }
     } else {
         samples.to_vec()
     };
@@ -244,30 +514,97 @@ fn encode_wav(samples: &[i16], original_sample_rate: u32) -> Result<Vec<u8>, Aud
     Ok(cursor.into_inner())
 }
 
-fn downsample(samples: &[i16], from: u32, to: u32) -> Vec<i16> {
-    if from == to {
-        return samples.to_vec();
+/// Re-encode a WAV produced by `encode_wav` into the upload format
+pub fn compress_wav(wav: Vec<u8>, encoding: AudioEncoding) -> Result<Vec<u8>, AudioError> {
+    let encoded = match encoding {
+        AudioEncoding::Wav => return Ok(wav),
+        AudioEncoding::Flac => {
+            let (samples, spec) = decode_wav(&wav)?;
+            flac::encode(&samples, spec.sample_rate, spec.channels)
+        }
+        AudioEncoding::Opus => {
+            let (samples, spec) = decode_wav(&wav)?;
+            opus::encode(&samples, spec.sample_rate, spec.channels).map_err(AudioError::EncodingError)?
+        }
+    };
+    println!("🗜️ Compressed upload to {} bytes ({:.0}% of WAV)", encoded.len(), encoded.len() as f64 * 100.0 / wav.len() as f64);
+    Ok(encoded)
+}
+
+fn decode_wav(wav: &[u8]) -> Result<(Vec<i16>, WavSpec), AudioError> {
+    let reader = WavReader::new(Cursor::new(wav))
+        .map_err(|e| AudioError::EncodingError(e.to_string()))?;
+    let spec = reader.spec();
+    let samples = reader
+        .into_samples::<i16>()
+        .collect::<Result<Vec<i16>, _>>()
+        .map_err(|e| AudioError::EncodingError(e.to_string()))?;
+    Ok((samples, spec))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_speech, mix_to_mono, AudioError};
+
+    #[test]
+    fn signed_integers_map_to_i16_range() {
+        assert_eq!(mix_to_mono(&[i8::MIN]), i16::MIN);
+        assert_eq!(mix_to_mono(&[0i8]), 0);
+        assert_eq!(mix_to_mono(&[i8::MAX]), 127 << 8);
+        assert_eq!(mix_to_mono(&[i16::MIN]), i16::MIN);
+        assert_eq!(mix_to_mono(&[i16::MAX]), i16::MAX);
+        assert_eq!(mix_to_mono(&[i32::MIN]), i16::MIN);
+        assert_eq!(mix_to_mono(&[0i32]), 0);
+        assert_eq!(mix_to_mono(&[i32::MAX]), i16::MAX);
+        assert_eq!(mix_to_mono(&[i64::MIN]), i16::MIN);
+        assert_eq!(mix_to_mono(&[0i64]), 0);
+        assert_eq!(mix_to_mono(&[i64::MAX]), i16::MAX);
     }
 
-    let ratio = from as f64 / to as f64;
-    let target_len = (samples.len() as f64 / ratio) as usize;
-    let mut result = Vec::with_capacity(target_len);
-
-    for i in 0..target_len {
-        let pos = i as f64 * ratio;
-        let index = pos as usize;
-
-        if index + 1 < samples.len() {
-            // Linear interpolation for clearer audio extraction
-            let fract = pos - index as f64;
-            let s1 = samples[index] as f64;
-            let s2 = samples[index + 1] as f64;
-            let interpolated = s1 + (s2 - s1) * fract;
-            result.push(interpolated as i16);
-        } else if index < samples.len() {
-            result.push(samples[index]);
-        }
+    #[test]
+    fn unsigned_integers_are_centred_on_their_midpoint() {
+        assert_eq!(mix_to_mono(&[u8::MIN]), i16::MIN);
+        assert_eq!(mix_to_mono(&[128u8]), 0);
+        assert_eq!(mix_to_mono(&[u8::MAX]), 127 << 8);
+        assert_eq!(mix_to_mono(&[u16::MIN]), i16::MIN);
+        assert_eq!(mix_to_mono(&[32_768u16]), 0);
+        assert_eq!(mix_to_mono(&[u16::MAX]), i16::MAX);
+        assert_eq!(mix_to_mono(&[u32::MIN]), i16::MIN);
+        assert_eq!(mix_to_mono(&[1u32 << 31]), 0);
+        assert_eq!(mix_to_mono(&[u32::MAX]), i16::MAX);
+        assert_eq!(mix_to_mono(&[u64::MIN]), i16::MIN);
+        assert_eq!(mix_to_mono(&[1u64 << 63]), 0);
+        assert_eq!(mix_to_mono(&[u64::MAX]), i16::MAX);
+    }
+
+    #[test]
+    fn floats_map_full_scale_to_i16_range() {
+        assert_eq!(mix_to_mono(&[-1.0f32]), i16::MIN);
+        assert_eq!(mix_to_mono(&[0.0f32]), 0);
+        assert_eq!(mix_to_mono(&[1.0f32]), i16::MAX);
+        assert_eq!(mix_to_mono(&[0.5f32]), 16_384);
+        assert_eq!(mix_to_mono(&[-1.0f64]), i16::MIN);
+        assert_eq!(mix_to_mono(&[0.0f64]), 0);
+        assert_eq!(mix_to_mono(&[1.0f64]), i16::MAX);
     }
 
-    result
+    #[test]
+    fn channels_are_averaged() {
+        assert_eq!(mix_to_mono(&[1_000i16, 3_000]), 2_000);
+        assert_eq!(mix_to_mono(&[1_000i16, 2_000, 3_000, 6_000]), 3_000);
+        assert_eq!(mix_to_mono(&[i16::MAX, i16::MAX]), i16::MAX);
+        assert_eq!(mix_to_mono(&[i16::MIN, i16::MIN, i16::MIN]), i16::MIN);
+        assert_eq!(mix_to_mono(&[0.5f32, -0.5]), 0);
+        assert_eq!(mix_to_mono(&[128u8, 128, 255, 1]), 0);
+        assert_eq!(mix_to_mono::<f32>(&[]), 0);
+    }
+
+    #[test]
+    fn speech_gate_rejects_taps_and_silence() {
+        let tone: Vec<i16> = (0..16_000).map(|n| if n % 20 < 10 { 8_000 } else { -8_000 }).collect();
+        assert_eq!(check_speech(&tone, 16_000).unwrap(), 1_000);
+        assert!(matches!(check_speech(&tone[..1_600], 16_000), Err(AudioError::TooShort(100))));
+        assert!(matches!(check_speech(&[0; 16_000], 16_000), Err(AudioError::Silent(_))));
+        assert!(matches!(check_speech(&[], 16_000), Err(AudioError::EncodingError(_))));
+    }
 }
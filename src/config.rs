@@ -1,9 +1,28 @@
 //! Configuration module for Speech-to-Text application
 //! Note: Users must provide their own API Key via the app settings.
 
+/// Default endpoints and models, overridable in settings
+pub const GROQ_BASE_URL: &str = "https://api.groq.com/openai/v1";
 pub const GROQ_API_URL: &str = "https://api.groq.com/openai/v1/audio/transcriptions";
 pub const WHISPER_MODEL: &str = "whisper-large-v3-turbo";
+pub const REFINE_MODEL: &str = "llama-3.3-70b-versatile";
 
 /// Audio Configuration
 pub const CHANNELS: u16 = 1;
 pub const BITS_PER_SAMPLE: u16 = 16;
+/// Capacity of the lock-free capture ring, in seconds of mono audio
+pub const RING_BUFFER_SECONDS: u32 = 2;
+
+/// Recordings shorter than this are treated as accidental hotkey taps
+pub const MIN_RECORDING_MS: u64 = 300;
+/// Recordings quieter than this overall (dBFS) are treated as silence
+pub const MIN_RMS_DB: f32 = -55.0;
+/// How often queued offline recordings are retried in the background
+pub const QUEUE_RETRY_SECS: u64 = 30;
+/// Streaming mode cuts a chunk at this length even without a pause
+pub const STREAM_MAX_CHUNK_MS: u32 = 30_000;
+
+/// Request body limit of the hosted Whisper APIs (Groq and OpenAI both allow 25 MB)
+pub const MAX_UPLOAD_BYTES: usize = 25 * 1024 * 1024;
+/// Audio repeated at the start of each piece when an oversized upload is split
+pub const SPLIT_OVERLAP_MS: u32 = 1_000;
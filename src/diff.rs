@@ -0,0 +1,83 @@
+//! Word-level diff between the raw transcription and the refined text
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffPart<'a> {
+    Same(&'a str),
+    /// Only in the raw transcription
+    Removed(&'a str),
+    /// Only in the refined text
+    Added(&'a str),
+}
+
+/// Longest-common-subsequence diff over whitespace-separated words
+pub fn word_diff<'a>(raw: &'a str, refined: &'a str) -> Vec<DiffPart<'a>> {
+    let old: Vec<&str> = raw.split_whitespace().collect();
+    let new: Vec<&str> = refined.split_whitespace().collect();
+
+    // lcs[i][j] = length of the LCS of old[i..] and new[j..]
+    let width = new.len() + 1;
+    let mut lcs = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i * width + j] = if old[i] == new[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut parts = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            parts.push(DiffPart::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[(i + 1) * width + j] >= lcs[i * width + j + 1] {
+            parts.push(DiffPart::Removed(old[i]));
+            i += 1;
+        } else {
+            parts.push(DiffPart::Added(new[j]));
+            j += 1;
+        }
+    }
+    parts.extend(old[i..].iter().map(|w| DiffPart::Removed(w)));
+    parts.extend(new[j..].iter().map(|w| DiffPart::Added(w)));
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{word_diff, DiffPart::*};
+
+    #[test]
+    fn identical_text_is_all_same() {
+        assert_eq!(word_diff("hello  there", "hello there"), [Same("hello"), Same("there")]);
+    }
+
+    #[test]
+    fn pure_insertion() {
+        assert_eq!(word_diff("hello there", "hello over there"), [Same("hello"), Added("over"), Same("there")]);
+    }
+
+    #[test]
+    fn pure_deletion() {
+        assert_eq!(word_diff("um hello uh there", "hello there"), [Removed("um"), Same("hello"), Removed("uh"), Same("there")]);
+    }
+
+    #[test]
+    fn replacement_shows_removed_then_added() {
+        assert_eq!(
+            word_diff("send it to jon tomorrow", "send it to John tomorrow."),
+            [Same("send"), Same("it"), Same("to"), Removed("jon"), Removed("tomorrow"), Added("John"), Added("tomorrow.")]
+        );
+    }
+
+    #[test]
+    fn empty_sides() {
+        assert_eq!(word_diff("", ""), []);
+        assert_eq!(word_diff("", "new words"), [Added("new"), Added("words")]);
+        assert_eq!(word_diff("old words", " "), [Removed("old"), Removed("words")]);
+    }
+}
@@ -0,0 +1,258 @@
+//! Persistent transcription history stored as JSON Lines in the app's data directory
+//! The whole file is loaded at startup; search and pagination run over the in-memory copy.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use directories::ProjectDirs;
+
+use crate::transcriber::Segment;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// What was known about a recording when it was made, kept with it through the offline queue
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct RecordingContext {
+    pub duration_ms: u64,
+    /// Speech model that handled the recording
+    pub model: String,
+    /// Title of the window that had focus when recording started
+    pub target_app: Option<String>,
+    /// Refinement mode the recording was made with; `None` for entries saved before it was recorded
+    pub refine_mode: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HistoryRecord {
+    /// Creation time in milliseconds since the Unix epoch, bumped where needed to keep ids unique
+    pub id: u64,
+    pub raw_text: String,
+    /// Final text after refinement; equal to `raw_text` when nothing changed it
+    pub refined_text: String,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(flatten)]
+    pub context: RecordingContext,
+    /// Transcribed from the offline queue after the fact, so it was not pasted
+    #[serde(default)]
+    pub delayed: bool,
+    #[serde(default)]
+    pub segments: Vec<Segment>,
+}
+
+impl HistoryRecord {
+    /// Seconds since the Unix epoch
+    pub fn timestamp(&self) -> u64 {
+        self.id / 1000
+    }
+
+    fn matches(&self, terms: &[String]) -> bool {
+        let haystack = format!(
+            "{}\n{}\n{}",
+            self.raw_text,
+            self.refined_text,
+            self.context.target_app.as_deref().unwrap_or_default()
+        )
+        .to_lowercase();
+        terms.iter().all(|term| haystack.contains(term))
+    }
+}
+
+pub struct HistoryStore {
+    path: Option<PathBuf>,
+    /// Oldest first, matching the file
+    records: Vec<HistoryRecord>,
+}
+
+impl HistoryStore {
+    /// Load the history file and drop entries older than `retention_days` (0 keeps everything)
+    pub fn open(retention_days: u32) -> Self {
+        let path = ProjectDirs::from("com", "ashmil", "speech_to_text")
+            .map(|proj_dirs| proj_dirs.data_dir().join("history.jsonl"));
+        Self::open_path(path, retention_days)
+    }
+
+    fn open_path(path: Option<PathBuf>, retention_days: u32) -> Self {
+        let records = path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .map(|content| {
+                content
+                    .lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .filter_map(|line| serde_json::from_str(line).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut store = Self { path, records };
+        if let Err(e) = store.apply_retention(retention_days) {
+            println!("⚠️ Could not prune history: {}", e);
+        }
+        store
+    }
+
+    pub fn add(&mut self, record: HistoryRecord) -> io::Result<()> {
+        let result = match &self.path {
+            Some(path) => {
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                let line = serde_json::to_string(&record).map_err(io::Error::other)?;
+                let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+                writeln!(file, "{}", line)
+            }
+            None => Ok(()),
+        };
+        self.records.push(record);
+        result
+    }
+
+    /// Newest-first records containing every whitespace-separated term of `query`, case-insensitively
+    pub fn search(&self, query: &str) -> Vec<&HistoryRecord> {
+        let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        self.records.iter().rev().filter(|r| r.matches(&terms)).collect()
+    }
+
+    /// Id for the next record: the current time, or one past the newest id when that is not later
+    pub fn next_id(&self) -> u64 {
+        let newest = self.records.iter().map(|r| r.id).max();
+        newest.map_or(now_ms(), |newest| now_ms().max(newest + 1))
+    }
+
+    pub fn get(&self, id: u64) -> Option<&HistoryRecord> {
+        self.records.iter().find(|r| r.id == id)
+    }
+
+    pub fn clear(&mut self) -> io::Result<()> {
+        self.records.clear();
+        self.rewrite()
+    }
+
+    /// Remove records older than `retention_days`; 0 means keep forever
+    pub fn apply_retention(&mut self, retention_days: u32) -> io::Result<()> {
+        if retention_days == 0 {
+            return Ok(());
+        }
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+        let cutoff = now.saturating_sub(retention_days as u64 * SECONDS_PER_DAY);
+
+        let before = self.records.len();
+        self.records.retain(|r| r.timestamp() >= cutoff);
+        if self.records.len() == before {
+            return Ok(());
+        }
+        println!("🧹 Removed {} history entries older than {} days", before - self.records.len(), retention_days);
+        self.rewrite()
+    }
+
+    /// Replace the file with the in-memory records, via a temp file so a crash cannot truncate it
+    fn rewrite(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut content = String::new();
+        for record in &self.records {
+            content.push_str(&serde_json::to_string(record).map_err(io::Error::other)?);
+            content.push('\n');
+        }
+        let temp_path = path.with_extension("jsonl.tmp");
+        fs::write(&temp_path, content)?;
+        fs::rename(temp_path, path)
+    }
+}
+
+/// Milliseconds since the Unix epoch
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcriber::DropReason;
+
+    fn segment(text: &str, dropped: Option<DropReason>) -> Segment {
+        Segment {
+            start: 0.0,
+            end: 1.5,
+            text: text.to_string(),
+            no_speech_prob: 0.9,
+            avg_logprob: -1.2,
+            compression_ratio: 1.1,
+            dropped,
+        }
+    }
+
+    #[test]
+    fn records_survive_a_restart_with_drop_reasons() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+
+        let mut store = HistoryStore::open_path(Some(path.clone()), 0);
+        store
+            .add(HistoryRecord {
+                id: store.next_id(),
+                raw_text: "hello there thanks for watching".to_string(),
+                refined_text: "Hello there.".to_string(),
+                language: Some("en".to_string()),
+                context: RecordingContext { duration_ms: 2_500, model: "whisper".to_string(), target_app: Some("Editor".to_string()), refine_mode: None },
+                delayed: false,
+                segments: vec![
+                    segment("hello there", None),
+                    segment("thanks for watching", Some(DropReason::LikelySilence)),
+                    segment("la la la la", Some(DropReason::Repetitive)),
+                ],
+            })
+            .unwrap();
+
+        let reopened = HistoryStore::open_path(Some(path), 0);
+        let records = reopened.search("");
+        assert_eq!(records.len(), 1);
+        let record = records[0];
+        assert_eq!(record.refined_text, "Hello there.");
+        assert_eq!(record.context.target_app.as_deref(), Some("Editor"));
+        let reasons: Vec<_> = record.segments.iter().map(|s| s.dropped).collect();
+        assert_eq!(reasons, [None, Some(DropReason::LikelySilence), Some(DropReason::Repetitive)]);
+    }
+
+    #[test]
+    fn ids_stay_unique_within_one_millisecond() {
+        let mut store = HistoryStore::open_path(None, 0);
+        let mut ids = Vec::new();
+        for text in ["one", "two", "three"] {
+            let id = store.next_id();
+            store
+                .add(HistoryRecord {
+                    id,
+                    raw_text: text.to_string(),
+                    refined_text: text.to_string(),
+                    language: None,
+                    context: RecordingContext::default(),
+                    delayed: false,
+                    segments: Vec::new(),
+                })
+                .unwrap();
+            ids.push(id);
+        }
+        assert!(ids.windows(2).all(|pair| pair[1] > pair[0]), "{:?}", ids);
+        assert_eq!(store.get(ids[1]).map(|r| r.raw_text.as_str()), Some("two"));
+    }
+
+    #[test]
+    fn segments_from_older_files_load_as_kept() {
+        let line = r#"{"id":1,"raw_text":"a","refined_text":"a","segments":[{"start":0.0,"end":1.0,"text":"a"}]}"#;
+        let record: HistoryRecord = serde_json::from_str(line).unwrap();
+        assert_eq!(record.segments[0].dropped, None);
+    }
+}
@@ -0,0 +1,329 @@
+//! Global hotkey combinations: parsing, validation and matching against the keyboard state
+//! Bindings are stored in settings as strings such as "F8", "Shift+Win" or "Ctrl+Alt+D".
+//! Reading the keyboard is platform-specific and lives in `listener`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A key that can end a hotkey combination
+pub struct KeySpec {
+    pub name: &'static str,
+    /// Windows virtual-key code
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub vk: u16,
+    /// Linux input event code (`KEY_*`); X11 key codes are these plus 8
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    pub evdev: u16,
+    /// Safe to bind without Ctrl, Alt or Win because it never types text
+    standalone: bool,
+}
+
+const fn key(name: &'static str, vk: u16, evdev: u16, standalone: bool) -> KeySpec {
+    KeySpec { name, vk, evdev, standalone }
+}
+
+pub const KEYS: &[KeySpec] = &[
+    key("F1", 0x70, 59, true), key("F2", 0x71, 60, true), key("F3", 0x72, 61, true), key("F4", 0x73, 62, true),
+    key("F5", 0x74, 63, true), key("F6", 0x75, 64, true), key("F7", 0x76, 65, true), key("F8", 0x77, 66, true),
+    key("F9", 0x78, 67, true), key("F10", 0x79, 68, true), key("F11", 0x7A, 87, true), key("F12", 0x7B, 88, true),
+    key("F13", 0x7C, 183, true), key("F14", 0x7D, 184, true), key("F15", 0x7E, 185, true), key("F16", 0x7F, 186, true),
+    key("F17", 0x80, 187, true), key("F18", 0x81, 188, true), key("F19", 0x82, 189, true), key("F20", 0x83, 190, true),
+    key("F21", 0x84, 191, true), key("F22", 0x85, 192, true), key("F23", 0x86, 193, true), key("F24", 0x87, 194, true),
+    key("Esc", 0x1B, 1, true), key("Pause", 0x13, 119, true), key("ScrollLock", 0x91, 70, true), key("Insert", 0x2D, 110, true),
+    key("PrintScreen", 0x2C, 99, true),
+    key("A", 0x41, 30, false), key("B", 0x42, 48, false), key("C", 0x43, 46, false), key("D", 0x44, 32, false),
+    key("E", 0x45, 18, false), key("F", 0x46, 33, false), key("G", 0x47, 34, false), key("H", 0x48, 35, false),
+    key("I", 0x49, 23, false), key("J", 0x4A, 36, false), key("K", 0x4B, 37, false), key("L", 0x4C, 38, false),
+    key("M", 0x4D, 50, false), key("N", 0x4E, 49, false), key("O", 0x4F, 24, false), key("P", 0x50, 25, false),
+    key("Q", 0x51, 16, false), key("R", 0x52, 19, false), key("S", 0x53, 31, false), key("T", 0x54, 20, false),
+    key("U", 0x55, 22, false), key("V", 0x56, 47, false), key("W", 0x57, 17, false), key("X", 0x58, 45, false),
+    key("Y", 0x59, 21, false), key("Z", 0x5A, 44, false),
+    key("0", 0x30, 11, false), key("1", 0x31, 2, false), key("2", 0x32, 3, false), key("3", 0x33, 4, false),
+    key("4", 0x34, 5, false), key("5", 0x35, 6, false), key("6", 0x36, 7, false), key("7", 0x37, 8, false),
+    key("8", 0x38, 9, false), key("9", 0x39, 10, false),
+    key("Space", 0x20, 57, false), key("Tab", 0x09, 15, false), key("Enter", 0x0D, 28, false), key("Backspace", 0x08, 14, false),
+    key("Delete", 0x2E, 111, false), key("Home", 0x24, 102, false), key("End", 0x23, 107, false), key("PageUp", 0x21, 104, false),
+    key("PageDown", 0x22, 109, false), key("Left", 0x25, 105, false), key("Up", 0x26, 103, false), key("Right", 0x27, 106, false),
+    key("Down", 0x28, 108, false), key("CapsLock", 0x14, 58, false), key("Backquote", 0xC0, 41, false), key("Minus", 0xBD, 12, false),
+    key("Equals", 0xBB, 13, false), key("Comma", 0xBC, 51, false), key("Period", 0xBE, 52, false), key("Slash", 0xBF, 53, false),
+];
+
+/// Linux input codes of the left and right variant of each modifier (`KEY_LEFTCTRL`, `KEY_RIGHTCTRL`, ...)
+#[cfg(target_os = "linux")]
+pub const CTRL_CODES: [u16; 2] = [29, 97];
+#[cfg(target_os = "linux")]
+pub const SHIFT_CODES: [u16; 2] = [42, 54];
+#[cfg(target_os = "linux")]
+pub const ALT_CODES: [u16; 2] = [56, 100];
+#[cfg(target_os = "linux")]
+pub const META_CODES: [u16; 2] = [125, 126];
+
+/// Keys held at one moment, as reported by a `HotkeyListener`
+#[derive(Clone, Debug, Default)]
+pub struct KeyboardState {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub win: bool,
+    /// Names from the key table of the other keys held
+    pub keys: Vec<&'static str>,
+}
+
+impl KeyboardState {
+    pub fn any_modifier(&self) -> bool {
+        self.ctrl || self.alt || self.shift || self.win
+    }
+}
+
+fn key_spec(name: &str) -> Option<&'static KeySpec> {
+    KEYS.iter().find(|k| k.name == name)
+}
+
+/// A hotkey and the refinement mode it records into; `None` means the selected mode
+pub type Binding = (Hotkey, Option<String>);
+
+/// Modifier keys plus an optional main key; modifier-only combos like Shift+Win are allowed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Hotkey {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub win: bool,
+    /// Canonical name from the key table, e.g. "F8"
+    pub key: Option<&'static str>,
+}
+
+impl Hotkey {
+    pub fn is_empty(&self) -> bool {
+        self.modifier_count() == 0 && self.key.is_none()
+    }
+
+    pub fn modifier_count(&self) -> usize {
+        [self.ctrl, self.alt, self.shift, self.win].iter().filter(|&&m| m).count()
+    }
+
+    /// Reject combinations that are incomplete or would swallow ordinary typing
+    pub fn validate(&self) -> Result<(), String> {
+        match self.key.and_then(key_spec) {
+            None if self.modifier_count() < 2 => {
+                Err(format!("{} needs a key or at least two modifiers", self))
+            }
+            Some(spec) if !(spec.standalone || self.ctrl || self.alt || self.win) => {
+                Err(format!("{} would block typing; add Ctrl, Alt or Win", self))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether every key of the combination is held in `state`; extra modifiers are ignored
+    pub fn is_down(&self, state: &KeyboardState) -> bool {
+        (!self.ctrl || state.ctrl)
+            && (!self.alt || state.alt)
+            && (!self.shift || state.shift)
+            && (!self.win || state.win)
+            && self.key.is_none_or(|key| state.keys.contains(&key))
+    }
+
+    /// Everything held in `state`, keeping only the first non-modifier key; used by the key-capture widget
+    pub fn from_state(state: &KeyboardState) -> Hotkey {
+        Hotkey {
+            ctrl: state.ctrl,
+            alt: state.alt,
+            shift: state.shift,
+            win: state.win,
+            key: state.keys.first().copied(),
+        }
+    }
+
+    /// Linux input codes to press in order for this combination, modifiers first
+    #[cfg(target_os = "linux")]
+    pub fn evdev_codes(&self) -> Vec<u16> {
+        let modifiers = [
+            (self.ctrl, CTRL_CODES[0]),
+            (self.alt, ALT_CODES[0]),
+            (self.shift, SHIFT_CODES[0]),
+            (self.win, META_CODES[0]),
+        ];
+        modifiers
+            .iter()
+            .filter(|(held, _)| *held)
+            .map(|(_, code)| *code)
+            .chain(self.key.and_then(key_spec).map(|k| k.evdev))
+            .collect()
+    }
+
+    /// Combine two snapshots so a capture keeps every key seen while the combo was built
+    pub fn merge(self, other: Hotkey) -> Hotkey {
+        Hotkey {
+            ctrl: self.ctrl || other.ctrl,
+            alt: self.alt || other.alt,
+            shift: self.shift || other.shift,
+            win: self.win || other.win,
+            key: other.key.or(self.key),
+        }
+    }
+}
+
+impl fmt::Display for Hotkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let modifiers = [(self.ctrl, "Ctrl"), (self.alt, "Alt"), (self.shift, "Shift"), (self.win, "Win")];
+        let parts: Vec<&str> = modifiers
+            .iter()
+            .filter(|(held, _)| *held)
+            .map(|(_, name)| *name)
+            .chain(self.key)
+            .collect();
+        write!(f, "{}", parts.join("+"))
+    }
+}
+
+impl FromStr for Hotkey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut hotkey = Hotkey::default();
+        for part in s.split('+').map(str::trim) {
+            match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => hotkey.ctrl = true,
+                "alt" => hotkey.alt = true,
+                "shift" => hotkey.shift = true,
+                "win" | "super" | "meta" => hotkey.win = true,
+                lower => {
+                    if hotkey.key.is_some() {
+                        return Err(format!("\"{}\" has more than one key", s));
+                    }
+                    let spec = KEYS
+                        .iter()
+                        .find(|k| k.name.eq_ignore_ascii_case(lower))
+                        .ok_or_else(|| format!("Unknown key \"{}\"", part))?;
+                    hotkey.key = Some(spec.name);
+                }
+            }
+        }
+        if hotkey.is_empty() {
+            return Err("Hotkey is empty".to_string());
+        }
+        Ok(hotkey)
+    }
+}
+
+impl Serialize for Hotkey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Hotkey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Parse a saved binding, skipping it with a warning when it is not valid, so one bad entry
+/// cannot make the whole settings file unreadable
+fn parse_saved(text: &str) -> Option<Hotkey> {
+    text.parse()
+        .inspect_err(|e| println!("⚠️ Ignoring saved hotkey \"{}\": {}", text, e))
+        .ok()
+}
+
+/// Serde helper for a list of bindings; entries that do not parse are dropped
+pub mod list {
+    use super::{parse_saved, Hotkey};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(hotkeys: &[Hotkey], serializer: S) -> Result<S::Ok, S::Error> {
+        hotkeys.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Hotkey>, D::Error> {
+        let texts = Vec::<String>::deserialize(deserializer)?;
+        Ok(texts.iter().filter_map(|text| parse_saved(text)).collect())
+    }
+}
+
+/// Serde helper for bindings keyed by another setting; entries that do not parse are dropped
+pub mod map {
+    use std::collections::HashMap;
+    use std::hash::Hash;
+
+    use super::{parse_saved, Hotkey};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<K: Serialize, S: Serializer>(hotkeys: &HashMap<K, Hotkey>, serializer: S) -> Result<S::Ok, S::Error> {
+        hotkeys.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, K, D>(deserializer: D) -> Result<HashMap<K, Hotkey>, D::Error>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        D: Deserializer<'de>,
+    {
+        let texts = HashMap::<K, String>::deserialize(deserializer)?;
+        Ok(texts.into_iter().filter_map(|(key, text)| Some((key, parse_saved(&text)?))).collect())
+    }
+}
+
+/// Serde helper for optional bindings stored as a string, where "" means unbound; a binding
+/// that does not parse is treated as unbound
+pub mod optional {
+    use super::{parse_saved, Hotkey};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(hotkey: &Option<Hotkey>, serializer: S) -> Result<S::Ok, S::Error> {
+        match hotkey {
+            Some(hotkey) => serializer.collect_str(hotkey),
+            None => serializer.serialize_str(""),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Hotkey>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        if text.trim().is_empty() {
+            return Ok(None);
+        }
+        Ok(parse_saved(&text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_prints_canonical_form() {
+        let hotkey: Hotkey = "super + shift".parse().unwrap();
+        assert_eq!(hotkey.to_string(), "Shift+Win");
+        let hotkey: Hotkey = "alt+control+d".parse().unwrap();
+        assert_eq!(hotkey.to_string(), "Ctrl+Alt+D");
+        assert_eq!("f8".parse::<Hotkey>().unwrap().key, Some("F8"));
+    }
+
+    #[test]
+    fn rejects_unknown_or_ambiguous_strings() {
+        assert!("".parse::<Hotkey>().is_err());
+        assert!("Ctrl+Hyper".parse::<Hotkey>().is_err());
+        assert!("Ctrl+A+B".parse::<Hotkey>().is_err());
+    }
+
+    #[test]
+    fn validation_keeps_typing_usable() {
+        let valid = |text: &str| text.parse::<Hotkey>().unwrap().validate().is_ok();
+        assert!(valid("F8"));
+        assert!(valid("Shift+Win"));
+        assert!(valid("Ctrl+Alt+D"));
+        assert!(!valid("Shift"));
+        assert!(!valid("D"));
+        assert!(!valid("Shift+D"));
+    }
+
+    #[test]
+    fn is_down_ignores_extra_modifiers() {
+        let state = KeyboardState { ctrl: true, shift: true, keys: vec!["F8"], ..Default::default() };
+        assert!("F8".parse::<Hotkey>().unwrap().is_down(&state));
+        assert!("Ctrl+F8".parse::<Hotkey>().unwrap().is_down(&state));
+        assert!(!"Alt+F8".parse::<Hotkey>().unwrap().is_down(&state));
+    }
+}
@@ -0,0 +1,281 @@
+//! Sends the paste shortcut to the focused window once the text is on the clipboard
+//! Windows uses `enigo`. Linux can use X11 XTest, `wtype` (Wayland virtual-keyboard protocol) or `ydotool` (uinput).
+
+use crate::hotkey::Hotkey;
+use crate::settings::InjectorKind;
+
+pub trait Injector {
+    /// Concrete backend, never `InjectorKind::Auto`; selects the paste shortcut to use
+    fn kind(&self) -> InjectorKind;
+
+    /// Press and release `shortcut` in whichever window has focus
+    fn press(&mut self, shortcut: Hotkey) -> Result<(), String>;
+}
+
+#[cfg(not(target_os = "linux"))]
+mod enigo_injector {
+    use std::thread;
+    use std::time::Duration;
+
+    use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+
+    use super::Injector;
+    use crate::hotkey::Hotkey;
+    use crate::settings::InjectorKind;
+
+    /// Gap between synthetic key events so slow applications register each one
+    const KEY_GAP_MS: u64 = 30;
+
+    pub struct EnigoInjector {
+        enigo: Enigo,
+    }
+
+    impl EnigoInjector {
+        pub fn new() -> Result<Self, String> {
+            let enigo = Enigo::new(&Settings::default()).map_err(|e| format!("Failed to initialize Enigo: {}", e))?;
+            Ok(Self { enigo })
+        }
+    }
+
+    impl Injector for EnigoInjector {
+        fn kind(&self) -> InjectorKind {
+            InjectorKind::Enigo
+        }
+
+        fn press(&mut self, shortcut: Hotkey) -> Result<(), String> {
+            let key = shortcut.key.and_then(enigo_key).ok_or_else(|| format!("{} cannot be sent", shortcut))?;
+            let modifiers: Vec<Key> = [
+                (shortcut.ctrl, Key::Control),
+                (shortcut.alt, Key::Alt),
+                (shortcut.shift, Key::Shift),
+                (shortcut.win, Key::Meta),
+            ]
+            .into_iter()
+            .filter_map(|(held, key)| held.then_some(key))
+            .collect();
+
+            for modifier in &modifiers {
+                self.enigo.key(*modifier, Direction::Press).map_err(|e| e.to_string())?;
+                thread::sleep(Duration::from_millis(KEY_GAP_MS));
+            }
+            let result = self.enigo.key(key, Direction::Click).map_err(|e| e.to_string());
+            thread::sleep(Duration::from_millis(KEY_GAP_MS));
+            // Release the modifiers even if the key failed, so none is left stuck down
+            for modifier in modifiers.iter().rev() {
+                let _ = self.enigo.key(*modifier, Direction::Release);
+            }
+            result
+        }
+    }
+
+    fn enigo_key(name: &str) -> Option<Key> {
+        match name {
+            // enigo has no Insert key on macOS
+            #[cfg(windows)]
+            "Insert" => Some(Key::Insert),
+            "Enter" => Some(Key::Return),
+            "Space" => Some(Key::Space),
+            "Tab" => Some(Key::Tab),
+            _ => {
+                let mut chars = name.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if c.is_ascii_alphanumeric() => Some(Key::Unicode(c.to_ascii_lowercase())),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod xtest {
+    use std::thread;
+    use std::time::Duration;
+
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{KEY_PRESS_EVENT, KEY_RELEASE_EVENT};
+    use x11rb::protocol::xtest::ConnectionExt as _;
+    use x11rb::rust_connection::RustConnection;
+    use x11rb::{CURRENT_TIME, NONE};
+
+    use super::Injector;
+    use crate::hotkey::Hotkey;
+    use crate::settings::InjectorKind;
+
+    /// X11 key codes are Linux input codes shifted by 8
+    const KEYCODE_OFFSET: u16 = 8;
+    const KEY_GAP_MS: u64 = 30;
+
+    pub struct XTestInjector {
+        conn: RustConnection,
+    }
+
+    impl XTestInjector {
+        pub fn connect() -> Result<Self, String> {
+            let (conn, _) = x11rb::connect(None).map_err(|e| format!("Cannot reach the X server: {}", e))?;
+            conn.xtest_get_version(2, 2)
+                .map_err(|e| e.to_string())?
+                .reply()
+                .map_err(|e| format!("XTest is not available: {}", e))?;
+            Ok(Self { conn })
+        }
+
+        fn send(&self, event: u8, code: u16) -> Result<(), String> {
+            let keycode = u8::try_from(code + KEYCODE_OFFSET).map_err(|_| format!("Key code {} is out of X11 range", code))?;
+            self.conn
+                .xtest_fake_input(event, keycode, CURRENT_TIME, NONE, 0, 0, 0)
+                .map_err(|e| e.to_string())?;
+            self.conn.flush().map_err(|e| e.to_string())?;
+            thread::sleep(Duration::from_millis(KEY_GAP_MS));
+            Ok(())
+        }
+    }
+
+    impl Injector for XTestInjector {
+        fn kind(&self) -> InjectorKind {
+            InjectorKind::XTest
+        }
+
+        fn press(&mut self, shortcut: Hotkey) -> Result<(), String> {
+            let codes = shortcut.evdev_codes();
+            let mut result = Ok(());
+            for &code in &codes {
+                result = result.and_then(|_| self.send(KEY_PRESS_EVENT, code));
+            }
+            // Release everything pressed, in reverse, even after a failure
+            for &code in codes.iter().rev() {
+                let _ = self.send(KEY_RELEASE_EVENT, code);
+            }
+            result
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod command {
+    use std::env;
+    use std::path::Path;
+    use std::process::Command;
+
+    use super::Injector;
+    use crate::hotkey::Hotkey;
+    use crate::settings::InjectorKind;
+
+    /// Whether `program` is an executable on `PATH`
+    pub fn on_path(program: &str) -> bool {
+        env::var_os("PATH").is_some_and(|paths| env::split_paths(&paths).any(|dir| Path::new(&dir).join(program).is_file()))
+    }
+
+    fn run(command: &mut Command) -> Result<(), String> {
+        let output = command.output().map_err(|e| e.to_string())?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+        }
+    }
+
+    /// Types through the Wayland virtual-keyboard protocol (wlroots compositors, KDE, Hyprland, ...)
+    pub struct WtypeInjector;
+
+    impl Injector for WtypeInjector {
+        fn kind(&self) -> InjectorKind {
+            InjectorKind::Wtype
+        }
+
+        fn press(&mut self, shortcut: Hotkey) -> Result<(), String> {
+            let key = shortcut.key.and_then(keysym).ok_or_else(|| format!("{} cannot be sent", shortcut))?;
+            let modifiers: Vec<&str> = [(shortcut.ctrl, "ctrl"), (shortcut.alt, "alt"), (shortcut.shift, "shift"), (shortcut.win, "logo")]
+                .into_iter()
+                .filter_map(|(held, name)| held.then_some(name))
+                .collect();
+
+            let mut command = Command::new("wtype");
+            for modifier in &modifiers {
+                command.args(["-M", modifier]);
+            }
+            command.args(["-k", &key]);
+            for modifier in modifiers.iter().rev() {
+                command.args(["-m", modifier]);
+            }
+            run(&mut command).map_err(|e| format!("wtype failed: {}", e))
+        }
+    }
+
+    /// Presses keys through `/dev/uinput`; works on any compositor but needs the `ydotoold` daemon running
+    pub struct YdotoolInjector;
+
+    impl Injector for YdotoolInjector {
+        fn kind(&self) -> InjectorKind {
+            InjectorKind::Ydotool
+        }
+
+        fn press(&mut self, shortcut: Hotkey) -> Result<(), String> {
+            // ydotool takes `<code>:<1|0>` pairs for press and release
+            let codes = shortcut.evdev_codes();
+            let presses = codes.iter().map(|code| format!("{}:1", code));
+            let releases = codes.iter().rev().map(|code| format!("{}:0", code));
+            let mut command = Command::new("ydotool");
+            command.arg("key").args(presses.chain(releases));
+            run(&mut command).map_err(|e| format!("ydotool failed: {}", e))
+        }
+    }
+
+    /// XKB keysym name for a key from the hotkey table
+    fn keysym(name: &str) -> Option<String> {
+        let special = match name {
+            "Enter" => "Return",
+            "Space" => "space",
+            "Esc" => "Escape",
+            "PageUp" => "Prior",
+            "PageDown" => "Next",
+            "PrintScreen" => "Print",
+            "ScrollLock" => "Scroll_Lock",
+            "CapsLock" => "Caps_Lock",
+            "Backspace" => "BackSpace",
+            "Backquote" => "grave",
+            "Minus" => "minus",
+            "Equals" => "equal",
+            "Comma" => "comma",
+            "Period" => "period",
+            "Slash" => "slash",
+            other if other.len() == 1 => return Some(other.to_ascii_lowercase()),
+            other => other,
+        };
+        Some(special.to_string())
+    }
+}
+
+/// Build the injector chosen in settings, or the best one for this session when set to automatic
+#[cfg(not(target_os = "linux"))]
+pub fn create_injector(kind: InjectorKind) -> Result<Box<dyn Injector>, String> {
+    match kind {
+        InjectorKind::Auto | InjectorKind::Enigo => Ok(Box::new(enigo_injector::EnigoInjector::new()?)),
+        other => Err(format!("{} is only available on Linux", other.label())),
+    }
+}
+
+/// Build the injector chosen in settings, or the best one for this session when set to automatic
+#[cfg(target_os = "linux")]
+pub fn create_injector(kind: InjectorKind) -> Result<Box<dyn Injector>, String> {
+    let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+    let kind = match kind {
+        // XTest only reaches XWayland windows, so prefer a Wayland-native tool when one is installed
+        InjectorKind::Auto if wayland && command::on_path("wtype") => InjectorKind::Wtype,
+        InjectorKind::Auto if wayland && command::on_path("ydotool") => InjectorKind::Ydotool,
+        InjectorKind::Auto => InjectorKind::XTest,
+        other => other,
+    };
+
+    let injector: Box<dyn Injector> = match kind {
+        InjectorKind::XTest => Box::new(xtest::XTestInjector::connect()?),
+        InjectorKind::Wtype if command::on_path("wtype") => Box::new(command::WtypeInjector),
+        InjectorKind::Ydotool if command::on_path("ydotool") => Box::new(command::YdotoolInjector),
+        InjectorKind::Wtype | InjectorKind::Ydotool => {
+            return Err(format!("{} is not installed", kind.label()));
+        }
+        InjectorKind::Enigo | InjectorKind::Auto => return Err("enigo is not used on Linux".to_string()),
+    };
+    println!("⌨️ Pasting via {}", injector.kind().label());
+    Ok(injector)
+}
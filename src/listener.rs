@@ -0,0 +1,249 @@
+//! Global keyboard listeners that feed the hotkey thread
+//! Windows polls `GetAsyncKeyState`. Linux uses XInput2 raw key events on X11 and reads `/dev/input`
+//! (evdev) under Wayland, where X11 clients cannot see keys pressed in native Wayland windows.
+
+use crate::hotkey::KeyboardState;
+
+/// Source of the global keyboard state, polled every few milliseconds from the hotkey thread
+pub trait HotkeyListener: Send {
+    fn name(&self) -> &str;
+
+    /// Keys held right now, whichever window has focus
+    fn poll(&mut self) -> KeyboardState;
+}
+
+#[cfg(windows)]
+mod windows {
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+        GetAsyncKeyState, VK_CONTROL, VK_LWIN, VK_MENU, VK_RWIN, VK_SHIFT,
+    };
+
+    use super::HotkeyListener;
+    use crate::hotkey::{KeyboardState, KEYS};
+
+    pub struct WindowsListener;
+
+    impl HotkeyListener for WindowsListener {
+        fn name(&self) -> &str {
+            "Windows"
+        }
+
+        fn poll(&mut self) -> KeyboardState {
+            KeyboardState {
+                ctrl: vk_down(VK_CONTROL),
+                alt: vk_down(VK_MENU),
+                shift: vk_down(VK_SHIFT),
+                win: vk_down(VK_LWIN) || vk_down(VK_RWIN),
+                keys: KEYS.iter().filter(|k| vk_down(k.vk)).map(|k| k.name).collect(),
+            }
+        }
+    }
+
+    fn vk_down(vk: u16) -> bool {
+        unsafe { (GetAsyncKeyState(vk as i32) as u16 & 0x8000) != 0 }
+    }
+}
+
+/// Build the keyboard state from a test for whether a Linux key code is held
+#[cfg(target_os = "linux")]
+fn state_from_codes(held: impl Fn(u16) -> bool) -> KeyboardState {
+    use crate::hotkey::{ALT_CODES, CTRL_CODES, META_CODES, SHIFT_CODES};
+
+    let any = |codes: [u16; 2]| codes.iter().any(|&code| held(code));
+    KeyboardState {
+        ctrl: any(CTRL_CODES),
+        alt: any(ALT_CODES),
+        shift: any(SHIFT_CODES),
+        win: any(META_CODES),
+        keys: crate::hotkey::KEYS.iter().filter(|k| held(k.evdev)).map(|k| k.name).collect(),
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod x11 {
+    use std::collections::HashSet;
+
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xinput::{self, ConnectionExt as _};
+    use x11rb::protocol::Event;
+    use x11rb::rust_connection::RustConnection;
+
+    use super::HotkeyListener;
+    use crate::hotkey::KeyboardState;
+
+    /// X11 key codes are Linux input codes shifted by 8
+    const KEYCODE_OFFSET: u32 = 8;
+
+    /// Tracks key state from XInput2 raw events on the root window. Unlike `XGrabKey` this does not
+    /// take the keys away from the focused application and also sees modifier-only combos like Shift+Win.
+    pub struct X11Listener {
+        conn: RustConnection,
+        pressed: HashSet<u16>,
+    }
+
+    impl X11Listener {
+        pub fn connect() -> Result<Self, String> {
+            let (conn, screen) = x11rb::connect(None).map_err(|e| format!("Cannot reach the X server: {}", e))?;
+            let root = conn.setup().roots[screen].root;
+
+            conn.xinput_xi_query_version(2, 0)
+                .map_err(|e| e.to_string())?
+                .reply()
+                .map_err(|e| format!("XInput2 is not available: {}", e))?;
+
+            let mask = xinput::EventMask {
+                deviceid: xinput::Device::ALL_MASTER.into(),
+                mask: vec![xinput::XIEventMask::RAW_KEY_PRESS | xinput::XIEventMask::RAW_KEY_RELEASE],
+            };
+            conn.xinput_xi_select_events(root, &[mask])
+                .map_err(|e| e.to_string())?
+                .check()
+                .map_err(|e| format!("Cannot listen for raw key events: {}", e))?;
+
+            Ok(Self { conn, pressed: HashSet::new() })
+        }
+    }
+
+    impl HotkeyListener for X11Listener {
+        fn name(&self) -> &str {
+            "X11 (XInput2)"
+        }
+
+        fn poll(&mut self) -> KeyboardState {
+            while let Ok(Some(event)) = self.conn.poll_for_event() {
+                match event {
+                    Event::XinputRawKeyPress(e) => {
+                        self.pressed.insert(e.detail.saturating_sub(KEYCODE_OFFSET) as u16);
+                    }
+                    Event::XinputRawKeyRelease(e) => {
+                        self.pressed.remove(&(e.detail.saturating_sub(KEYCODE_OFFSET) as u16));
+                    }
+                    _ => {}
+                }
+            }
+            super::state_from_codes(|code| self.pressed.contains(&code))
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod evdev {
+    use std::fs::{self, File};
+    use std::os::fd::AsRawFd;
+    use std::time::{Duration, Instant};
+
+    use super::HotkeyListener;
+    use crate::hotkey::KeyboardState;
+
+    const INPUT_DIR: &str = "/dev/input";
+    /// `KEY_MAX` from linux/input-event-codes.h, rounded up to whole bytes of the key bitmap
+    const KEY_BITMAP_BYTES: usize = 0x2ff / 8 + 1;
+    const EV_KEY: u32 = 1;
+    const KEY_A: u16 = 30;
+    const KEY_ENTER: u16 = 28;
+    /// Keyboards plugged in later are picked up on the next rescan
+    const RESCAN_INTERVAL: Duration = Duration::from_secs(5);
+
+    /// `_IOC(_IOC_READ, 'E', nr, len)` from linux/input.h
+    const fn eviocg(nr: u32, len: usize) -> u32 {
+        (2 << 30) | ((len as u32) << 16) | ((b'E' as u32) << 8) | nr
+    }
+    /// `EVIOCGBIT(EV_KEY, len)`: key codes a device can report
+    const EVIOCGBIT_KEY: u32 = eviocg(0x20 + EV_KEY, KEY_BITMAP_BYTES);
+    /// `EVIOCGKEY(len)`: key codes currently held
+    const EVIOCGKEY: u32 = eviocg(0x18, KEY_BITMAP_BYTES);
+
+    type KeyBitmap = [u8; KEY_BITMAP_BYTES];
+
+    /// Reads the held keys straight from the kernel, so it works under Wayland and on any desktop.
+    /// Needs read access to `/dev/input/event*`, usually by being in the `input` group.
+    pub struct EvdevListener {
+        keyboards: Vec<File>,
+        last_scan: Instant,
+    }
+
+    impl EvdevListener {
+        pub fn open() -> Result<Self, String> {
+            let keyboards = find_keyboards();
+            if keyboards.is_empty() {
+                return Err(format!("No readable keyboard in {} - add your user to the \"input\" group", INPUT_DIR));
+            }
+            println!("⌨️ Listening to {} keyboard device(s)", keyboards.len());
+            Ok(Self { keyboards, last_scan: Instant::now() })
+        }
+    }
+
+    impl HotkeyListener for EvdevListener {
+        fn name(&self) -> &str {
+            "evdev"
+        }
+
+        fn poll(&mut self) -> KeyboardState {
+            if self.last_scan.elapsed() >= RESCAN_INTERVAL {
+                self.keyboards = find_keyboards();
+                self.last_scan = Instant::now();
+            }
+
+            let mut held: KeyBitmap = [0; KEY_BITMAP_BYTES];
+            for keyboard in &self.keyboards {
+                if let Some(bitmap) = read_bitmap(keyboard, EVIOCGKEY) {
+                    held.iter_mut().zip(bitmap).for_each(|(all, device)| *all |= device);
+                }
+            }
+            super::state_from_codes(|code| has_bit(&held, code))
+        }
+    }
+
+    /// Every readable event device that has letter keys
+    fn find_keyboards() -> Vec<File> {
+        let Ok(entries) = fs::read_dir(INPUT_DIR) else {
+            return Vec::new();
+        };
+        entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().starts_with("event"))
+            .filter_map(|e| File::open(e.path()).ok())
+            .filter(|device| {
+                read_bitmap(device, EVIOCGBIT_KEY).is_some_and(|keys| has_bit(&keys, KEY_A) && has_bit(&keys, KEY_ENTER))
+            })
+            .collect()
+    }
+
+    fn read_bitmap(device: &File, request: u32) -> Option<KeyBitmap> {
+        let mut bitmap: KeyBitmap = [0; KEY_BITMAP_BYTES];
+        let result = unsafe { libc::ioctl(device.as_raw_fd(), request as _, bitmap.as_mut_ptr()) };
+        (result >= 0).then_some(bitmap)
+    }
+
+    fn has_bit(bitmap: &KeyBitmap, code: u16) -> bool {
+        bitmap.get(code as usize / 8).is_some_and(|byte| byte & (1 << (code % 8)) != 0)
+    }
+}
+
+/// Pick the listener for this platform and session
+#[cfg(windows)]
+pub fn create_listener() -> Result<Box<dyn HotkeyListener>, String> {
+    Ok(Box::new(windows::WindowsListener))
+}
+
+/// Pick the listener for this platform and session
+#[cfg(target_os = "linux")]
+pub fn create_listener() -> Result<Box<dyn HotkeyListener>, String> {
+    let x11 = || x11::X11Listener::connect().map(|l| Box::new(l) as Box<dyn HotkeyListener>);
+    let evdev = || evdev::EvdevListener::open().map(|l| Box::new(l) as Box<dyn HotkeyListener>);
+
+    // Under Wayland an X11 connection goes to XWayland, which only sees keys meant for X11 windows
+    let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some()
+        || std::env::var("XDG_SESSION_TYPE").is_ok_and(|session| session == "wayland");
+    let result = if wayland {
+        evdev().or_else(|evdev_error| x11().map_err(|x11_error| format!("{}; {}", evdev_error, x11_error)))
+    } else {
+        x11().or_else(|x11_error| evdev().map_err(|evdev_error| format!("{}; {}", x11_error, evdev_error)))
+    };
+    result.inspect(|listener| println!("⌨️ Hotkeys via {}", listener.name()))
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+pub fn create_listener() -> Result<Box<dyn HotkeyListener>, String> {
+    Err("Global hotkeys are not supported on this platform yet".to_string())
+}
@@ -0,0 +1,145 @@
+//! Offline transcription backend running whisper.cpp on GGML/GGUF models
+//! Only functional when built with `--features local-whisper`.
+
+use crate::transcriber::{SpeechBackend, TranscriptionError, TranscriptionResponse};
+
+pub struct LocalWhisperBackend {
+    model_path: String,
+    threads: u32,
+}
+
+impl LocalWhisperBackend {
+    pub fn new(model_path: &str, threads: u32) -> Self {
+        Self {
+            model_path: model_path.to_string(),
+            threads: threads.max(1),
+        }
+    }
+}
+
+#[cfg(feature = "local-whisper")]
+mod engine {
+    use std::io::Cursor;
+    use std::sync::{Arc, Mutex};
+
+    use hound::WavReader;
+    use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+    use super::LocalWhisperBackend;
+    use crate::transcriber::{Segment, TranscriptionError, TranscriptionResponse};
+
+    /// Loaded model, kept alive between recordings since loading can take seconds
+    static MODEL_CACHE: Mutex<Option<(String, Arc<WhisperContext>)>> = Mutex::new(None);
+
+    fn load_context(model_path: &str) -> Result<Arc<WhisperContext>, TranscriptionError> {
+        let mut cache = MODEL_CACHE.lock()
+            .map_err(|_| TranscriptionError::RequestError("Model cache poisoned".to_string()))?;
+
+        if let Some((path, ctx)) = cache.as_ref() {
+            if path == model_path {
+                return Ok(ctx.clone());
+            }
+        }
+
+        println!("📂 Loading Whisper model from {}...", model_path);
+        let ctx = WhisperContext::new_with_params(model_path, WhisperContextParameters::default())
+            .map(Arc::new)
+            .map_err(|e| TranscriptionError::RequestError(format!("Failed to load model: {}", e)))?;
+        *cache = Some((model_path.to_string(), ctx.clone()));
+        Ok(ctx)
+    }
+
+    /// Decode the 16 kHz mono WAV from `audio::encode_wav` into normalized f32 samples
+    fn decode_wav(audio_data: &[u8]) -> Result<Vec<f32>, TranscriptionError> {
+        let reader = WavReader::new(Cursor::new(audio_data))
+            .map_err(|e| TranscriptionError::ParseError(format!("Invalid WAV: {}", e)))?;
+
+        reader
+            .into_samples::<i16>()
+            .map(|s| s.map(|v| v as f32 / 32768.0))
+            .collect::<Result<Vec<f32>, _>>()
+            .map_err(|e| TranscriptionError::ParseError(format!("Invalid WAV: {}", e)))
+    }
+
+    pub fn transcribe(
+        backend: &LocalWhisperBackend,
+        audio_data: Vec<u8>,
+    ) -> Result<TranscriptionResponse, TranscriptionError> {
+        let samples = decode_wav(&audio_data)?;
+        let ctx = load_context(&backend.model_path)?;
+        let mut state = ctx.create_state()
+            .map_err(|e| TranscriptionError::RequestError(e.to_string()))?;
+
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        params.set_n_threads(backend.threads as i32);
+        params.set_language(Some("auto"));
+        params.set_temperature(0.0);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_special(false);
+        params.set_print_timestamps(false);
+
+        println!("🖥️ Transcribing {} samples locally on {} threads...", samples.len(), backend.threads);
+        state.full(params, &samples)
+            .map_err(|e| TranscriptionError::RequestError(e.to_string()))?;
+
+        let segment_count = state.full_n_segments()
+            .map_err(|e| TranscriptionError::ParseError(e.to_string()))?;
+        let mut text = String::new();
+        let mut segments = Vec::new();
+        for i in 0..segment_count {
+            let segment_text = state.full_get_segment_text_lossy(i)
+                .map_err(|e| TranscriptionError::ParseError(e.to_string()))?;
+            text.push_str(&segment_text);
+
+            // Timestamps are in 10 ms units; whisper.cpp exposes no confidence scores here
+            segments.push(Segment {
+                start: state.full_get_segment_t0(i).unwrap_or(0) as f64 / 100.0,
+                end: state.full_get_segment_t1(i).unwrap_or(0) as f64 / 100.0,
+                text: segment_text,
+                no_speech_prob: 0.0,
+                avg_logprob: 0.0,
+                compression_ratio: 0.0,
+                dropped: None,
+            });
+        }
+
+        let language = state.full_lang_id_from_state()
+            .ok()
+            .and_then(whisper_rs::get_lang_str)
+            .map(str::to_string);
+
+        Ok(TranscriptionResponse {
+            text: text.trim().to_string(),
+            language,
+            segments,
+            raw_text: None,
+            refine_error: None,
+        })
+    }
+}
+
+impl SpeechBackend for LocalWhisperBackend {
+    fn name(&self) -> &str {
+        "Local Whisper"
+    }
+
+    fn transcribe(&self, audio_data: Vec<u8>) -> Result<TranscriptionResponse, TranscriptionError> {
+        if self.model_path.trim().is_empty() {
+            return Err(TranscriptionError::ApiError("No Whisper model selected. Please set it in Settings.".to_string()));
+        }
+
+        #[cfg(feature = "local-whisper")]
+        {
+            engine::transcribe(self, audio_data)
+        }
+
+        #[cfg(not(feature = "local-whisper"))]
+        {
+            let _ = (audio_data, self.threads);
+            Err(TranscriptionError::ApiError(
+                "This build has no offline support. Rebuild with --features local-whisper.".to_string(),
+            ))
+        }
+    }
+}
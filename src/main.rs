@@ -4,8 +4,21 @@
 mod app;
 mod audio;
 mod config;
+mod diff;
+mod flac;
+mod history;
+mod hotkey;
+mod injector;
+mod listener;
+mod local_whisper;
+mod opus;
+mod queue;
+mod refiner;
+mod resample;
+mod retry;
 mod transcriber;
 mod settings;
+mod vad;
 
 use app::VoxMagicApp;
 use eframe::egui;
@@ -0,0 +1,101 @@
+//! Ogg/Opus encoder for low-bandwidth uploads
+//! Only functional when built with `--features opus`.
+
+#[cfg(feature = "opus")]
+mod engine {
+    use audiopus::coder::Encoder;
+    use audiopus::{Application, Bitrate, Channels, SampleRate};
+    use ogg::writing::{PacketWriteEndInfo, PacketWriter};
+
+    /// Target bitrate; plenty for speech recognition
+    const BITRATE: i32 = 24_000;
+    /// Opus granule positions always count 48 kHz samples
+    const GRANULE_RATE: u64 = 48_000;
+    /// Encoder delay the decoder trims from the start (libopus lookahead at 48 kHz)
+    const PRE_SKIP: u16 = 312;
+    /// Opus frames are 20 ms
+    const FRAMES_PER_SECOND: u32 = 50;
+    /// Upper bound on one encoded packet, as recommended by libopus
+    const MAX_PACKET_BYTES: usize = 4000;
+
+    pub fn encode(samples: &[i16], sample_rate: u32, channels: u16) -> Result<Vec<u8>, String> {
+        let rate = SampleRate::try_from(sample_rate as i32)
+            .map_err(|_| format!("Opus does not support {} Hz", sample_rate))?;
+        let layout = match channels {
+            1 => Channels::Mono,
+            2 => Channels::Stereo,
+            n => return Err(format!("Opus upload supports 1 or 2 channels, got {}", n)),
+        };
+
+        let mut encoder = Encoder::new(rate, layout, Application::Voip).map_err(|e| e.to_string())?;
+        encoder.set_bitrate(Bitrate::BitsPerSecond(BITRATE)).map_err(|e| e.to_string())?;
+
+        let mut output = Vec::new();
+        let mut writer = PacketWriter::new(&mut output);
+        let serial = 1;
+
+        let mut head = b"OpusHead".to_vec();
+        head.push(1); // Version
+        head.push(channels as u8);
+        head.extend_from_slice(&PRE_SKIP.to_le_bytes());
+        head.extend_from_slice(&sample_rate.to_le_bytes());
+        head.extend_from_slice(&0i16.to_le_bytes()); // Output gain
+        head.push(0); // Mapping family: mono/stereo
+        writer.write_packet(head, serial, PacketWriteEndInfo::EndPage, 0).map_err(|e| e.to_string())?;
+
+        let vendor = env!("CARGO_PKG_NAME").as_bytes();
+        let mut tags = b"OpusTags".to_vec();
+        tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+        tags.extend_from_slice(vendor);
+        tags.extend_from_slice(&0u32.to_le_bytes()); // No user comments
+        writer.write_packet(tags, serial, PacketWriteEndInfo::EndPage, 0).map_err(|e| e.to_string())?;
+
+        let frame_len = (sample_rate / FRAMES_PER_SECOND) as usize * channels as usize;
+        let total_frames = samples.len() / channels as usize;
+        let final_granule = PRE_SKIP as u64 + total_frames as u64 * GRANULE_RATE / sample_rate as u64;
+        let frames: Vec<&[i16]> = samples.chunks(frame_len).collect();
+        let mut packet = [0u8; MAX_PACKET_BYTES];
+        let mut padded = vec![0i16; frame_len];
+
+        for (i, frame) in frames.iter().enumerate() {
+            // The last frame is zero-padded; the final granule position tells the decoder where to stop
+            let input = if frame.len() == frame_len {
+                *frame
+            } else {
+                padded[..frame.len()].copy_from_slice(frame);
+                padded[frame.len()..].fill(0);
+                &padded
+            };
+            let len = encoder.encode(input, &mut packet).map_err(|e| e.to_string())?;
+
+            let is_last = i + 1 == frames.len();
+            let granule = PRE_SKIP as u64 + (i as u64 + 1) * GRANULE_RATE / FRAMES_PER_SECOND as u64;
+            let (end_info, granule) = if is_last {
+                (PacketWriteEndInfo::EndStream, final_granule)
+            } else {
+                (PacketWriteEndInfo::NormalPacket, granule)
+            };
+            writer.write_packet(packet[..len].to_vec(), serial, end_info, granule).map_err(|e| e.to_string())?;
+        }
+
+        drop(writer);
+        Ok(output)
+    }
+}
+
+/// Whether this build can encode Opus
+pub const AVAILABLE: bool = cfg!(feature = "opus");
+
+/// Encode interleaved 16-bit samples into an Ogg/Opus stream
+pub fn encode(samples: &[i16], sample_rate: u32, channels: u16) -> Result<Vec<u8>, String> {
+    #[cfg(feature = "opus")]
+    {
+        engine::encode(samples, sample_rate, channels)
+    }
+
+    #[cfg(not(feature = "opus"))]
+    {
+        let _ = (samples, sample_rate, channels);
+        Err("This build has no Opus support. Rebuild with --features opus.".to_string())
+    }
+}
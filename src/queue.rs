@@ -0,0 +1,149 @@
+//! On-disk queue for recordings that could not be transcribed because the service was unreachable
+//! Each entry is a `<id>.wav` with a `<id>.json` metadata file beside it. Entries that fail
+//! for a reason retrying cannot fix are moved to `failed/`.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use directories::ProjectDirs;
+
+use crate::history::RecordingContext;
+use crate::transcriber::TranscriptionError;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct QueuedRecording {
+    /// Roughly the Unix time in milliseconds when the recording was queued, unique and increasing; also the file stem
+    pub id: u64,
+    pub attempts: u32,
+    pub last_error: String,
+    #[serde(default)]
+    pub context: RecordingContext,
+}
+
+fn queue_dir() -> io::Result<PathBuf> {
+    let proj_dirs = ProjectDirs::from("com", "ashmil", "speech_to_text")
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No home directory"))?;
+    let dir = proj_dirs.data_dir().join("queue");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+impl QueuedRecording {
+    fn audio_path(&self) -> io::Result<PathBuf> {
+        Ok(queue_dir()?.join(format!("{}.wav", self.id)))
+    }
+
+    fn meta_path(&self) -> io::Result<PathBuf> {
+        Ok(queue_dir()?.join(format!("{}.json", self.id)))
+    }
+
+    pub fn load_audio(&self) -> io::Result<Vec<u8>> {
+        fs::read(self.audio_path()?)
+    }
+
+    /// Note another failed attempt in the metadata file
+    pub fn record_failure(&mut self, error: &TranscriptionError) -> io::Result<()> {
+        self.attempts += 1;
+        self.last_error = error.to_string();
+        self.save()
+    }
+
+    /// Move the entry out of the retry queue into `failed/`, keeping the audio so it can be recovered by hand
+    pub fn set_aside(&self) -> io::Result<PathBuf> {
+        let dir = queue_dir()?.join("failed");
+        fs::create_dir_all(&dir)?;
+        fs::rename(self.audio_path()?, dir.join(format!("{}.wav", self.id)))?;
+        fs::rename(self.meta_path()?, dir.join(format!("{}.json", self.id)))?;
+        Ok(dir)
+    }
+
+    pub fn remove(&self) -> io::Result<()> {
+        // The audio may already be gone if an earlier removal was interrupted
+        let _ = fs::remove_file(self.audio_path()?);
+        fs::remove_file(self.meta_path()?)
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(self.meta_path()?, json)
+    }
+}
+
+/// Store a recording that failed with `error` so it can be retried later
+pub fn enqueue(audio_data: &[u8], error: &TranscriptionError, context: RecordingContext) -> io::Result<QueuedRecording> {
+    // Audio first, so a metadata file never points at a missing recording
+    let (id, mut audio_file) = claim_audio_file(&queue_dir()?)?;
+    audio_file.write_all(audio_data)?;
+    let entry = QueuedRecording { id, attempts: 1, last_error: error.to_string(), context };
+    entry.save()?;
+    println!("💾 Queued recording {} for later ({})", id, error);
+    Ok(entry)
+}
+
+/// Create `<id>.wav` for a new entry, with an id later than every queued one.
+/// `create_new` keeps two recordings queued in the same millisecond from sharing a file.
+fn claim_audio_file(dir: &Path) -> io::Result<(u64, File)> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
+    let newest = fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.path().file_stem()?.to_str()?.parse::<u64>().ok())
+        .max();
+    let mut id = newest.map_or(now, |newest| now.max(newest + 1));
+
+    loop {
+        match OpenOptions::new().write(true).create_new(true).open(dir.join(format!("{}.wav", id))) {
+            Ok(file) => return Ok((id, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => id += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Queued recordings, oldest first; entries whose metadata cannot be read are skipped
+pub fn list() -> Vec<QueuedRecording> {
+    let Ok(entries) = queue_dir().and_then(fs::read_dir) else {
+        return Vec::new();
+    };
+
+    let mut queued: Vec<QueuedRecording> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|e| fs::read_to_string(e.path()).ok())
+        .filter_map(|content| serde_json::from_str(&content).ok())
+        .collect();
+    queued.sort_by_key(|q: &QueuedRecording| q.id);
+    queued
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::claim_audio_file;
+
+    #[test]
+    fn claimed_ids_are_unique_and_increasing() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let ids: Vec<u64> = (0..5).map(|_| claim_audio_file(dir.path()).unwrap().0).collect();
+
+        assert!(ids.windows(2).all(|w| w[0] < w[1]), "{:?}", ids);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 5);
+    }
+
+    #[test]
+    fn ids_follow_entries_from_the_future() {
+        let dir = tempfile::tempdir().unwrap();
+        // An entry queued while the clock was ahead
+        fs::write(dir.path().join(format!("{}.json", u64::MAX / 2)), "{}").unwrap();
+
+        let (id, _) = claim_audio_file(dir.path()).unwrap();
+
+        assert_eq!(id, u64::MAX / 2 + 1);
+    }
+}
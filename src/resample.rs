@@ -0,0 +1,185 @@
+//! Sample-rate conversion for the 16 kHz Whisper pipeline
+//! `linear` is the original fast path; `sinc` is a band-limited polyphase resampler.
+
+/// Filter half-length in zero crossings of the sinc; longer gives a sharper transition band
+const HALF_ZERO_CROSSINGS: f64 = 16.0;
+/// Cutoff as a fraction of the output Nyquist, leaving room for the transition band
+const ROLLOFF: f64 = 0.92;
+/// Above this many phases the coefficient table gets too big and taps are computed on the fly
+const MAX_TABLE_PHASES: u32 = 1024;
+
+/// Linear interpolation without an anti-aliasing filter (fast, but aliases when downsampling)
+pub fn linear(samples: &[i16], from: u32, to: u32) -> Vec<i16> {
+    if from == to {
+        return samples.to_vec();
+    }
+
+    let ratio = from as f64 / to as f64;
+    let target_len = (samples.len() as f64 / ratio) as usize;
+    let mut result = Vec::with_capacity(target_len);
+
+    for i in 0..target_len {
+        let pos = i as f64 * ratio;
+        let index = pos as usize;
+
+        if index + 1 < samples.len() {
+            // Linear interpolation for clearer audio extraction
+            let fract = pos - index as f64;
+            let s1 = samples[index] as f64;
+            let s2 = samples[index + 1] as f64;
+            let interpolated = s1 + (s2 - s1) * fract;
+            result.push(interpolated as i16);
+        } else if index < samples.len() {
+            result.push(samples[index]);
+        }
+    }
+
+    result
+}
+
+/// Blackman-windowed sinc resampler. The rate ratio is reduced to `up / down`
+/// and one filter phase is precomputed per distinct output offset.
+pub fn sinc(samples: &[i16], from: u32, to: u32) -> Vec<i16> {
+    if from == to || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let divisor = gcd(from, to);
+    let up = (to / divisor) as u64;
+    let down = (from / divisor) as u64;
+
+    // Normalized to the input Nyquist; only lowered when downsampling
+    let cutoff = (to as f64 / from as f64).min(1.0) * ROLLOFF;
+    let half = (HALF_ZERO_CROSSINGS / cutoff).ceil() as usize;
+    let taps = 2 * half;
+
+    let table = (up <= MAX_TABLE_PHASES as u64).then(|| {
+        let mut table = Vec::with_capacity(up as usize * taps);
+        for phase in 0..up {
+            let frac = phase as f64 / up as f64;
+            table.extend((0..taps).map(|j| coefficient(frac + (half - 1) as f64 - j as f64, cutoff, half)));
+        }
+        table
+    });
+
+    let target_len = (samples.len() as u64 * up / down) as usize;
+    let mut result = Vec::with_capacity(target_len);
+
+    for i in 0..target_len as u64 {
+        let position = i * down;
+        let base = (position / up) as isize;
+        let phase = position % up;
+        let first = base - (half as isize - 1);
+
+        let mut acc = 0.0;
+        for j in 0..taps {
+            let index = first + j as isize;
+            if index < 0 || index as usize >= samples.len() {
+                continue;
+            }
+            let weight = match &table {
+                Some(table) => table[phase as usize * taps + j],
+                None => coefficient(phase as f64 / up as f64 + (half - 1) as f64 - j as f64, cutoff, half),
+            };
+            acc += samples[index as usize] as f64 * weight;
+        }
+        result.push(acc.round().clamp(i16::MIN as f64, i16::MAX as f64) as i16);
+    }
+
+    result
+}
+
+/// Filter tap at `distance` input samples from the output position
+fn coefficient(distance: f64, cutoff: f64, half: usize) -> f64 {
+    let half = half as f64;
+    if distance.abs() >= half {
+        return 0.0;
+    }
+
+    let x = std::f64::consts::PI * cutoff * distance;
+    let sinc = if x.abs() < 1e-12 { 1.0 } else { x.sin() / x };
+
+    let phase = std::f64::consts::PI * distance / half;
+    let window = 0.42 + 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos();
+
+    cutoff * sinc * window
+}
+
+fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AMPLITUDE: f64 = 10_000.0;
+
+    fn tone(freq: f64, rate: u32, secs: f64) -> Vec<i16> {
+        let len = (rate as f64 * secs) as usize;
+        (0..len)
+            .map(|n| (AMPLITUDE * (2.0 * std::f64::consts::PI * freq * n as f64 / rate as f64).sin()) as i16)
+            .collect()
+    }
+
+    /// RMS relative to the input tone, ignoring the filter's ramp-up at both ends
+    fn relative_level(samples: &[i16]) -> f64 {
+        let edge = 200;
+        let body = &samples[edge..samples.len() - edge];
+        let power = body.iter().map(|&s| (s as f64).powi(2)).sum::<f64>() / body.len() as f64;
+        power.sqrt() / (AMPLITUDE / 2f64.sqrt())
+    }
+
+    #[test]
+    fn same_rate_is_passthrough() {
+        let input = tone(440.0, 16_000, 0.1);
+        assert_eq!(sinc(&input, 16_000, 16_000), input);
+        assert_eq!(linear(&input, 16_000, 16_000), input);
+    }
+
+    #[test]
+    fn output_length_follows_ratio() {
+        assert_eq!(sinc(&tone(440.0, 48_000, 1.0), 48_000, 16_000).len(), 16_000);
+        assert_eq!(sinc(&tone(440.0, 44_100, 1.0), 44_100, 16_000).len(), 16_000);
+        assert_eq!(sinc(&tone(440.0, 8_000, 1.0), 8_000, 16_000).len(), 16_000);
+    }
+
+    #[test]
+    fn passband_tone_keeps_its_level() {
+        for from in [48_000, 44_100, 22_050] {
+            let level = relative_level(&sinc(&tone(1_000.0, from, 0.5), from, 16_000));
+            assert!((0.99..1.01).contains(&level), "{} Hz: level {}", from, level);
+        }
+    }
+
+    #[test]
+    fn sinc_rejects_tones_above_output_nyquist_from_48k() {
+        // 10 kHz folds down to 6 kHz at 16 kHz unless it is filtered out first
+        let input = tone(10_000.0, 48_000, 0.5);
+        let linear_level = relative_level(&linear(&input, 48_000, 16_000));
+        let sinc_level = relative_level(&sinc(&input, 48_000, 16_000));
+        assert!(linear_level > 0.5, "linear level {}", linear_level);
+        assert!(sinc_level < 0.01, "sinc level {}", sinc_level);
+    }
+
+    #[test]
+    fn sinc_rejects_tones_above_output_nyquist_from_44k1() {
+        let input = tone(11_000.0, 44_100, 0.5);
+        let linear_level = relative_level(&linear(&input, 44_100, 16_000));
+        let sinc_level = relative_level(&sinc(&input, 44_100, 16_000));
+        assert!(linear_level > 0.5, "linear level {}", linear_level);
+        assert!(sinc_level < 0.01, "sinc level {}", sinc_level);
+    }
+
+    #[test]
+    fn transition_band_tone_is_attenuated_more_than_linear() {
+        // 7.5 kHz sits between the rolloff and the 8 kHz output Nyquist
+        let input = tone(7_500.0, 48_000, 0.5);
+        let linear_level = relative_level(&linear(&input, 48_000, 16_000));
+        let sinc_level = relative_level(&sinc(&input, 48_000, 16_000));
+        assert!(sinc_level < linear_level, "sinc {} vs linear {}", sinc_level, linear_level);
+    }
+}
@@ -0,0 +1,300 @@
+//! Retry with jittered exponential backoff for the HTTP backends
+//! Server hints (`Retry-After`, Groq's `x-ratelimit-*`) take precedence over the computed delay.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use reqwest::blocking::{RequestBuilder, Response};
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+
+use crate::transcriber::TranscriptionError;
+
+/// Rate-limit buckets reported by Groq, each with `remaining-*` and `reset-*` headers
+const RATE_LIMIT_BUCKETS: [&str; 2] = ["requests", "tokens"];
+
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    /// Total tries including the first; 1 disables retrying
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for each one after
+    pub base_delay_ms: u64,
+    /// Longest single wait; a server asking for more than this is not retried
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { max_attempts: 4, base_delay_ms: 500, max_delay_ms: 10_000 }
+    }
+}
+
+/// Receives a human-readable line each time a request is about to be retried
+pub type RetryObserver = Arc<dyn Fn(String) + Send + Sync>;
+
+#[derive(Clone, Default)]
+pub struct Retrier {
+    policy: RetryPolicy,
+    observer: Option<RetryObserver>,
+}
+
+impl Retrier {
+    pub fn new(policy: RetryPolicy, observer: Option<RetryObserver>) -> Self {
+        Self { policy, observer }
+    }
+
+    /// Send the request built by `build` until it succeeds, fails permanently or runs out of attempts.
+    /// The last response is returned even when unsuccessful so callers can report the API's error body.
+    pub fn send(
+        &self,
+        what: &str,
+        build: impl Fn() -> Result<RequestBuilder, TranscriptionError>,
+    ) -> Result<Response, TranscriptionError> {
+        let attempts = self.policy.max_attempts.max(1);
+        let max_delay = Duration::from_millis(self.policy.max_delay_ms);
+
+        let mut attempt = 1;
+        loop {
+            let last = attempt >= attempts;
+            let (reason, delay) = match build()?.send() {
+                Ok(response) => {
+                    let status = response.status();
+                    if !is_retryable(status) || last {
+                        return Ok(response);
+                    }
+                    let delay = match server_delay(response.headers()) {
+                        Some(wait) if wait > max_delay => {
+                            println!("⏳ {} asks to wait {:.0}s, giving up", what, wait.as_secs_f32());
+                            return Ok(response);
+                        }
+                        Some(wait) => wait,
+                        None => self.backoff(attempt),
+                    };
+                    (format!("{} returned {}", what, status.as_u16()), delay)
+                }
+                Err(e) if !last && (e.is_timeout() || e.is_connect()) => {
+                    (format!("{} unreachable", what), self.backoff(attempt))
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            let message = format!("{}, retrying in {:.1}s ({}/{})", reason, delay.as_secs_f32(), attempt + 1, attempts);
+            println!("🔁 {}", message);
+            if let Some(observer) = &self.observer {
+                observer(message);
+            }
+            thread::sleep(delay);
+            attempt += 1;
+        }
+    }
+
+    /// Exponential delay for `attempt` with "equal jitter" (uniform between half and full)
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = self.policy.base_delay_ms.saturating_mul(1 << (attempt - 1).min(16));
+        let capped = exponential.min(self.policy.max_delay_ms);
+        Duration::from_millis(capped / 2 + random() % (capped / 2 + 1))
+    }
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::REQUEST_TIMEOUT || status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// How long the server asked us to wait, from `Retry-After` or an exhausted rate-limit bucket
+pub fn server_delay(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(str::trim);
+
+    let retry_after = header("retry-after")
+        .and_then(|v| v.parse::<f64>().ok())
+        .map(Duration::from_secs_f64);
+
+    let exhausted = RATE_LIMIT_BUCKETS
+        .iter()
+        .filter(|bucket| header(&format!("x-ratelimit-remaining-{}", bucket)) == Some("0"))
+        .filter_map(|bucket| header(&format!("x-ratelimit-reset-{}", bucket)).and_then(parse_duration));
+
+    retry_after.into_iter().chain(exhausted).max()
+}
+
+/// Parse Groq's reset durations such as `120ms`, `7.66s` or `2m59.56s`
+fn parse_duration(value: &str) -> Option<Duration> {
+    let mut rest = value;
+    let mut seconds = 0.0;
+    while !rest.is_empty() {
+        let number_len = rest.find(|c: char| !(c.is_ascii_digit() || c == '.'))?;
+        let (number, tail) = rest.split_at(number_len);
+        let unit_len = tail.find(|c: char| c.is_ascii_digit()).unwrap_or(tail.len());
+        let (unit, next) = tail.split_at(unit_len);
+
+        let scale = match unit {
+            "h" => 3600.0,
+            "m" => 60.0,
+            "s" => 1.0,
+            "ms" => 0.001,
+            _ => return None,
+        };
+        seconds += number.parse::<f64>().ok()? * scale;
+        rest = next;
+    }
+    (seconds > 0.0).then(|| Duration::from_secs_f64(seconds))
+}
+
+/// Cheap randomness for jitter; `RandomState` is seeded differently for every instance
+fn random() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    /// Serve one canned response per connection, in order; returns the URL and a request counter
+    fn stub_server(responses: Vec<&'static str>) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        thread::spawn(move || {
+            for (stream, head) in listener.incoming().zip(responses) {
+                let mut stream = stream.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let n = stream.read(&mut buf).unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buf[..n]);
+                }
+                counter.fetch_add(1, Ordering::SeqCst);
+                let response = format!("HTTP/1.1 {}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n", head);
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (url, requests)
+    }
+
+    fn retrier(max_attempts: u32, max_delay_ms: u64) -> (Retrier, Arc<Mutex<Vec<String>>>) {
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let sink = messages.clone();
+        let observer: RetryObserver = Arc::new(move |message| sink.lock().unwrap().push(message));
+        let policy = RetryPolicy { max_attempts, base_delay_ms: 1, max_delay_ms };
+        (Retrier::new(policy, Some(observer)), messages)
+    }
+
+    fn send(retrier: &Retrier, url: &str) -> Result<Response, TranscriptionError> {
+        let client = reqwest::blocking::Client::new();
+        retrier.send("Stub", || Ok(client.get(url)))
+    }
+
+    #[test]
+    fn retries_rate_limit_and_server_errors_until_success() {
+        let (url, requests) = stub_server(vec![
+            "429 Too Many Requests\r\nretry-after: 0",
+            "503 Service Unavailable",
+            "200 OK",
+        ]);
+        let (retrier, messages) = retrier(4, 1_000);
+
+        let response = send(&retrier, &url).unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+        let messages = messages.lock().unwrap();
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].starts_with("Stub returned 429, retrying in 0.0s (2/4)"), "{}", messages[0]);
+        assert!(messages[1].starts_with("Stub returned 503, retrying in"), "{}", messages[1]);
+        assert!(messages[1].ends_with("(3/4)"), "{}", messages[1]);
+    }
+
+    #[test]
+    fn gives_up_when_server_wait_exceeds_max_delay() {
+        let (url, requests) = stub_server(vec!["429 Too Many Requests\r\nretry-after: 60", "200 OK"]);
+        let (retrier, messages) = retrier(4, 1_000);
+
+        let response = send(&retrier, &url).unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        assert!(messages.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn returns_last_response_when_attempts_run_out() {
+        let (url, requests) = stub_server(vec!["503 Service Unavailable", "502 Bad Gateway", "200 OK"]);
+        let (retrier, messages) = retrier(2, 1_000);
+
+        let response = send(&retrier, &url).unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert_eq!(messages.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn client_errors_are_not_retried() {
+        let (url, requests) = stub_server(vec!["401 Unauthorized", "200 OK"]);
+        let (retrier, messages) = retrier(4, 1_000);
+
+        let response = send(&retrier, &url).unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        assert!(messages.lock().unwrap().is_empty());
+    }
+
+    fn assert_secs(duration: Option<Duration>, expected: f64) {
+        let actual = duration.expect("a duration").as_secs_f64();
+        assert!((actual - expected).abs() < 1e-6, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn parses_groq_reset_durations() {
+        assert_secs(parse_duration("2m59.56s"), 179.56);
+        assert_secs(parse_duration("120ms"), 0.12);
+        assert_secs(parse_duration("7.66s"), 7.66);
+        assert_secs(parse_duration("1h2m"), 3720.0);
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("0s"), None);
+        assert_eq!(parse_duration("soon"), None);
+        assert_eq!(parse_duration("5"), None);
+        assert_eq!(parse_duration("5x"), None);
+        assert_eq!(parse_duration("1.2.3s"), None);
+    }
+
+    #[test]
+    fn server_delay_uses_longest_hint() {
+        let headers = |pairs: &[(&'static str, &'static str)]| {
+            let mut map = HeaderMap::new();
+            for &(name, value) in pairs {
+                map.insert(name, HeaderValue::from_static(value));
+            }
+            map
+        };
+
+        assert_eq!(server_delay(&headers(&[])), None);
+        assert_secs(server_delay(&headers(&[("retry-after", "3")])), 3.0);
+        assert_eq!(server_delay(&headers(&[("retry-after", "Wed, 21 Oct 2015 07:28:00 GMT")])), None);
+
+        // Only exhausted buckets count
+        let tokens_left = headers(&[
+            ("x-ratelimit-remaining-requests", "0"),
+            ("x-ratelimit-reset-requests", "2m"),
+            ("x-ratelimit-remaining-tokens", "5"),
+            ("x-ratelimit-reset-tokens", "10m"),
+        ]);
+        assert_secs(server_delay(&tokens_left), 120.0);
+
+        let both = headers(&[
+            ("retry-after", "30"),
+            ("x-ratelimit-remaining-tokens", "0"),
+            ("x-ratelimit-reset-tokens", "1m0.5s"),
+        ]);
+        assert_secs(server_delay(&both), 60.5);
+    }
+}
@@ -1,12 +1,262 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use directories::ProjectDirs;
 
+use crate::audio::ProcessingOptions;
+use crate::config::{GROQ_API_URL, GROQ_BASE_URL, REFINE_MODEL, STREAM_MAX_CHUNK_MS, WHISPER_MODEL};
+use crate::hotkey::{Binding, Hotkey};
+use crate::opus;
+use crate::refiner::{builtin_modes, DEFAULT_MODE};
+use crate::retry::RetryPolicy;
+use crate::transcriber::SegmentFilter;
+use crate::vad::{ChunkerConfig, VadConfig};
+
+/// Which speech-to-text provider handles transcription
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum SpeechBackendKind {
+    #[default]
+    Groq,
+    /// Any server exposing the OpenAI `/audio/transcriptions` endpoint (OpenAI, Azure proxy, self-hosted)
+    OpenAiCompatible,
+    /// Offline whisper.cpp inference on a GGML/GGUF model file
+    LocalWhisper,
+}
+
+impl SpeechBackendKind {
+    pub const ALL: [SpeechBackendKind; 3] = [
+        SpeechBackendKind::Groq,
+        SpeechBackendKind::OpenAiCompatible,
+        SpeechBackendKind::LocalWhisper,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SpeechBackendKind::Groq => "Groq Cloud",
+            SpeechBackendKind::OpenAiCompatible => "OpenAI-compatible",
+            SpeechBackendKind::LocalWhisper => "Local Whisper (offline)",
+        }
+    }
+}
+
+/// Which engine polishes the raw transcription
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum RefinerKind {
+    /// OpenAI-compatible chat completion endpoint (Groq, Ollama, llama.cpp server, LM Studio)
+    #[default]
+    Chat,
+    /// Paste the raw transcription as-is
+    Off,
+}
+
+impl RefinerKind {
+    pub const ALL: [RefinerKind; 2] = [RefinerKind::Chat, RefinerKind::Off];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RefinerKind::Chat => "Chat Model",
+            RefinerKind::Off => "Off (raw text)",
+        }
+    }
+}
+
+/// A named refinement style whose prompt replaces the default cleanup rules
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RefineMode {
+    pub name: String,
+    /// Instructions sent as the system prompt along with the transcript
+    pub prompt: String,
+    /// Hotkey that records straight into this mode
+    #[serde(default, with = "crate::hotkey::optional")]
+    pub hotkey: Option<Hotkey>,
+}
+
+/// Sample-rate converter used to reach 16 kHz
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ResamplerKind {
+    /// Linear interpolation, cheapest but aliases high frequencies into the speech band
+    Fast,
+    /// Band-limited windowed-sinc filter
+    #[default]
+    HighQuality,
+}
+
+impl ResamplerKind {
+    pub const ALL: [ResamplerKind; 2] = [ResamplerKind::Fast, ResamplerKind::HighQuality];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ResamplerKind::Fast => "Fast (linear)",
+            ResamplerKind::HighQuality => "High Quality (sinc)",
+        }
+    }
+}
+
+/// How the recording hotkey starts and stops a recording
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum TriggerMode {
+    /// Record while the hotkey is held
+    #[default]
+    PushToTalk,
+    /// First tap starts, the next tap stops
+    Toggle,
+    /// Hold to talk, or double-tap to keep recording hands-free until the next tap
+    DoubleTapLock,
+}
+
+impl TriggerMode {
+    pub const ALL: [TriggerMode; 3] = [TriggerMode::PushToTalk, TriggerMode::Toggle, TriggerMode::DoubleTapLock];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TriggerMode::PushToTalk => "Hold to talk",
+            TriggerMode::Toggle => "Tap to start / stop",
+            TriggerMode::DoubleTapLock => "Hold, or double-tap to lock",
+        }
+    }
+}
+
+/// How the paste shortcut is sent to the focused window
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum InjectorKind {
+    /// enigo on Windows; on Linux wtype or ydotool under Wayland, XTest on X11
+    #[default]
+    Auto,
+    Enigo,
+    /// X11 XTest extension; under Wayland it only reaches XWayland windows
+    XTest,
+    /// Wayland virtual-keyboard protocol through the `wtype` command
+    Wtype,
+    /// Kernel uinput device through the `ydotool` command and its daemon
+    Ydotool,
+}
+
+impl InjectorKind {
+    pub const ALL: [InjectorKind; 5] =
+        [InjectorKind::Auto, InjectorKind::Enigo, InjectorKind::XTest, InjectorKind::Wtype, InjectorKind::Ydotool];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            InjectorKind::Auto => "Automatic",
+            InjectorKind::Enigo => "System (enigo)",
+            InjectorKind::XTest => "X11 XTest",
+            InjectorKind::Wtype => "wtype (Wayland)",
+            InjectorKind::Ydotool => "ydotool (uinput)",
+        }
+    }
+}
+
+/// Container/codec of the audio sent to HTTP backends
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum AudioEncoding {
+    /// Uncompressed 16-bit PCM, about 32 KB per second
+    #[default]
+    Wav,
+    /// Lossless, usually around half the size of WAV for speech
+    Flac,
+    /// Lossy Ogg/Opus at 24 kbps, roughly a tenth of WAV (needs the `opus` feature)
+    Opus,
+}
+
+impl AudioEncoding {
+    /// Encodings this build can produce
+    #[cfg(feature = "opus")]
+    pub const ALL: [AudioEncoding; 3] = [AudioEncoding::Wav, AudioEncoding::Flac, AudioEncoding::Opus];
+    #[cfg(not(feature = "opus"))]
+    pub const ALL: [AudioEncoding; 2] = [AudioEncoding::Wav, AudioEncoding::Flac];
+
+    /// The encoding actually used for uploads; Opus falls back to FLAC in builds without the `opus` feature
+    pub fn supported(self) -> AudioEncoding {
+        match self {
+            AudioEncoding::Opus if !opus::AVAILABLE => AudioEncoding::Flac,
+            other => other,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AudioEncoding::Wav => "WAV (uncompressed)",
+            AudioEncoding::Flac => "FLAC (lossless)",
+            AudioEncoding::Opus => "Opus (smallest)",
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            AudioEncoding::Wav => "audio/wav",
+            AudioEncoding::Flac => "audio/flac",
+            AudioEncoding::Opus => "audio/ogg",
+        }
+    }
+
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            AudioEncoding::Wav => "audio.wav",
+            AudioEncoding::Flac => "audio.flac",
+            AudioEncoding::Opus => "audio.ogg",
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
 pub struct AppSettings {
     pub groq_api_key: String,
     pub auto_paste: bool,
     pub always_on_top: bool,
+    /// Hold any of these to record with the selected refinement mode
+    #[serde(with = "crate::hotkey::list")]
+    pub hotkeys: Vec<Hotkey>,
+    /// Discards the recording or transcription in progress without pasting
+    #[serde(with = "crate::hotkey::optional")]
+    pub cancel_hotkey: Option<Hotkey>,
+    pub trigger_mode: TriggerMode,
+    /// Recordings are stopped and transcribed automatically after this long; 0 means no limit
+    pub max_recording_secs: u32,
+    pub injector: InjectorKind,
+    /// Paste shortcut per injector, e.g. Ctrl+Shift+V for terminals; missing entries use Ctrl+V
+    #[serde(with = "crate::hotkey::map")]
+    pub paste_shortcuts: HashMap<InjectorKind, Hotkey>,
+    /// Microphone name as reported by the OS; empty means the system default
+    pub input_device: String,
+    pub resampler: ResamplerKind,
+    pub speech_backend: SpeechBackendKind,
+    /// Format of uploads to Groq and OpenAI-compatible servers
+    pub upload_encoding: AudioEncoding,
+    pub transcription_url: String,
+    pub whisper_model: String,
+    pub compatible_api_url: String,
+    pub compatible_api_key: String,
+    pub compatible_model: String,
+    pub local_model_path: String,
+    pub local_threads: u32,
+    pub refiner: RefinerKind,
+    pub refine_base_url: String,
+    pub refine_model: String,
+    pub refine_temperature: f32,
+    /// Leave empty to reuse the Groq key when refining through Groq
+    pub refine_api_key: String,
+    pub refine_modes: Vec<RefineMode>,
+    /// Name of the mode used by the main hotkey
+    pub refine_mode: String,
+    pub vad_enabled: bool,
+    pub vad_threshold_db: f32,
+    pub vad_max_gap_ms: u32,
+    pub vad_padding_ms: u32,
+    pub filter_hallucinations: bool,
+    pub max_no_speech_prob: f32,
+    pub min_avg_logprob: f32,
+    pub max_compression_ratio: f32,
+    /// Transcribe pause-delimited chunks in the background while still recording
+    pub streaming_mode: bool,
+    pub stream_min_chunk_secs: u32,
+    pub stream_pause_ms: u32,
+    /// Extra attempts after a timeout, 429 or 5xx; 0 disables retrying
+    pub max_retries: u32,
+    pub retry_base_delay_ms: u64,
+    pub retry_max_delay_ms: u64,
+    /// Days to keep transcription history; 0 keeps it forever
+    pub history_retention_days: u32,
 }
 
 impl Default for AppSettings {
@@ -15,19 +265,215 @@ impl Default for AppSettings {
             groq_api_key: String::new(),
             auto_paste: true,
             always_on_top: true,
+            hotkeys: vec![
+                Hotkey { key: Some("F8"), ..Default::default() },
+                Hotkey { shift: true, win: true, ..Default::default() },
+            ],
+            cancel_hotkey: Some(Hotkey { key: Some("Esc"), ..Default::default() }),
+            trigger_mode: TriggerMode::PushToTalk,
+            max_recording_secs: 600,
+            injector: InjectorKind::Auto,
+            paste_shortcuts: HashMap::new(),
+            input_device: String::new(),
+            resampler: ResamplerKind::HighQuality,
+            speech_backend: SpeechBackendKind::Groq,
+            upload_encoding: AudioEncoding::Wav,
+            transcription_url: GROQ_API_URL.to_string(),
+            whisper_model: WHISPER_MODEL.to_string(),
+            compatible_api_url: String::new(),
+            compatible_api_key: String::new(),
+            compatible_model: "whisper-1".to_string(),
+            local_model_path: String::new(),
+            local_threads: std::thread::available_parallelism()
+                .map(|n| n.get().min(8) as u32)
+                .unwrap_or(4),
+            refiner: RefinerKind::Chat,
+            refine_base_url: GROQ_BASE_URL.to_string(),
+            refine_model: REFINE_MODEL.to_string(),
+            refine_temperature: 0.1,
+            refine_api_key: String::new(),
+            refine_modes: builtin_modes(),
+            refine_mode: DEFAULT_MODE.to_string(),
+            vad_enabled: true,
+            vad_threshold_db: -45.0,
+            vad_max_gap_ms: 800,
+            vad_padding_ms: 200,
+            filter_hallucinations: true,
+            max_no_speech_prob: 0.6,
+            min_avg_logprob: -1.0,
+            max_compression_ratio: 2.4,
+            streaming_mode: false,
+            stream_min_chunk_secs: 8,
+            stream_pause_ms: 600,
+            max_retries: 3,
+            retry_base_delay_ms: 500,
+            retry_max_delay_ms: 10_000,
+            history_retention_days: 30,
         }
     }
 }
 
 impl AppSettings {
+    /// True when the selected backend is missing the details it needs to run
+    pub fn needs_setup(&self) -> bool {
+        match self.speech_backend {
+            SpeechBackendKind::Groq => self.groq_api_key.trim().is_empty(),
+            SpeechBackendKind::OpenAiCompatible => self.compatible_api_url.trim().is_empty(),
+            SpeechBackendKind::LocalWhisper => self.local_model_path.trim().is_empty(),
+        }
+    }
+
+    /// Model name recorded in history for the selected backend
+    pub fn speech_model(&self) -> String {
+        match self.speech_backend {
+            SpeechBackendKind::Groq => self.whisper_model.trim().to_string(),
+            SpeechBackendKind::OpenAiCompatible => self.compatible_model.trim().to_string(),
+            SpeechBackendKind::LocalWhisper => std::path::Path::new(self.local_model_path.trim())
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Every hotkey, main bindings first
+    pub fn bindings(&self) -> Vec<Binding> {
+        let main = self.hotkeys.iter().map(|hotkey| (*hotkey, None));
+        let modes = self
+            .refine_modes
+            .iter()
+            .filter_map(|mode| mode.hotkey.map(|hotkey| (hotkey, Some(mode.name.clone()))));
+        main.chain(modes).collect()
+    }
+
+    /// The selected refinement mode, falling back to the first one (or the built-in default)
+    pub fn active_refine_mode(&self) -> RefineMode {
+        self.refine_modes
+            .iter()
+            .find(|m| m.name == self.refine_mode)
+            .or_else(|| self.refine_modes.first())
+            .cloned()
+            .unwrap_or_else(|| builtin_modes().swap_remove(0))
+    }
+
+    pub fn vad_config(&self) -> Option<VadConfig> {
+        self.vad_enabled.then_some(VadConfig {
+            threshold_db: self.vad_threshold_db,
+            max_gap_ms: self.vad_max_gap_ms,
+            padding_ms: self.vad_padding_ms,
+        })
+    }
+
+    pub fn chunker_config(&self) -> Option<ChunkerConfig> {
+        self.streaming_mode.then_some(ChunkerConfig {
+            threshold_db: self.vad_threshold_db,
+            min_chunk_ms: self.stream_min_chunk_secs * 1000,
+            max_chunk_ms: STREAM_MAX_CHUNK_MS.max(self.stream_min_chunk_secs * 1000),
+            pause_ms: self.stream_pause_ms,
+        })
+    }
+
+    pub fn processing_options(&self) -> ProcessingOptions {
+        ProcessingOptions {
+            vad: self.vad_config(),
+            resampler: self.resampler,
+        }
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.max_retries + 1,
+            base_delay_ms: self.retry_base_delay_ms,
+            max_delay_ms: self.retry_max_delay_ms.max(self.retry_base_delay_ms),
+        }
+    }
+
+    pub fn segment_filter(&self) -> Option<SegmentFilter> {
+        self.filter_hallucinations.then_some(SegmentFilter {
+            max_no_speech_prob: self.max_no_speech_prob,
+            min_avg_logprob: self.min_avg_logprob,
+            max_compression_ratio: self.max_compression_ratio,
+        })
+    }
+
+    /// Check the endpoints and models the current configuration will use
+    pub fn validate(&self) -> Result<(), String> {
+        match self.speech_backend {
+            SpeechBackendKind::Groq => {
+                validate_endpoint(&self.transcription_url).map_err(|e| format!("Transcription URL: {}", e))?;
+                validate_model(&self.whisper_model).map_err(|e| format!("Whisper model: {}", e))?;
+            }
+            SpeechBackendKind::OpenAiCompatible => {
+                validate_endpoint(&self.compatible_api_url).map_err(|e| format!("Server URL: {}", e))?;
+                validate_model(&self.compatible_model).map_err(|e| format!("Server model: {}", e))?;
+            }
+            SpeechBackendKind::LocalWhisper => {}
+        }
+
+        if self.refiner == RefinerKind::Chat {
+            validate_base_url(&self.refine_base_url).map_err(|e| format!("Chat Base URL: {}", e))?;
+            validate_model(&self.refine_model).map_err(|e| format!("Chat model: {}", e))?;
+        }
+
+        self.validate_hotkeys()?;
+
+        let mut names = Vec::new();
+        for mode in &self.refine_modes {
+            let name = mode.name.trim();
+            if name.is_empty() {
+                return Err("Refinement modes need a name".to_string());
+            }
+            if names.contains(&name) {
+                return Err(format!("Mode \"{}\" is defined twice", name));
+            }
+            names.push(name);
+        }
+        Ok(())
+    }
+
+    /// Shortcut that pastes the clipboard when text is sent through `kind`
+    pub fn paste_shortcut(&self, kind: InjectorKind) -> Hotkey {
+        self.paste_shortcuts
+            .get(&kind)
+            .copied()
+            .unwrap_or(Hotkey { ctrl: true, key: Some("V"), ..Default::default() })
+    }
+
+    /// Every binding must be usable on its own and bound only once
+    pub fn validate_hotkeys(&self) -> Result<(), String> {
+        if self.hotkeys.is_empty() {
+            return Err("Add at least one recording hotkey".to_string());
+        }
+        let mut bound = Vec::new();
+        let cancel = self.cancel_hotkey.map(|hotkey| (hotkey, None));
+        for (hotkey, _) in self.bindings().into_iter().chain(cancel) {
+            hotkey.validate()?;
+            if bound.contains(&hotkey) {
+                return Err(format!("{} is already bound", hotkey));
+            }
+            bound.push(hotkey);
+        }
+        Ok(())
+    }
+
     pub fn load() -> Self {
         if let Some(proj_dirs) = ProjectDirs::from("com", "ashmil", "speech_to_text") {
             let config_dir = proj_dirs.config_dir();
             let config_path = config_dir.join("settings.json");
 
-            if let Ok(content) = fs::read_to_string(config_path) {
-                if let Ok(settings) = serde_json::from_str::<AppSettings>(&content) {
-                    return settings;
+            if let Ok(content) = fs::read_to_string(&config_path) {
+                match serde_json::from_str::<AppSettings>(&content) {
+                    Ok(mut settings) => {
+                        if settings.hotkeys.is_empty() {
+                            settings.hotkeys = Self::default().hotkeys;
+                        }
+                        return settings;
+                    }
+                    Err(e) => {
+                        // Keep the unreadable file, since the next save replaces it with defaults
+                        let backup = config_path.with_extension("json.bak");
+                        println!("⚠️ Could not read settings ({}), using defaults; old file kept as {}", e, backup.display());
+                        let _ = fs::copy(&config_path, backup);
+                    }
                 }
             }
         }
@@ -46,3 +492,81 @@ impl AppSettings {
         Ok(())
     }
 }
+
+/// Accept only absolute http(s) URLs with a host
+pub fn validate_endpoint(url: &str) -> Result<(), String> {
+    let parsed = reqwest::Url::parse(url.trim()).map_err(|e| e.to_string())?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err("must start with http:// or https://".to_string());
+    }
+    if parsed.host_str().is_none() {
+        return Err("missing host".to_string());
+    }
+    Ok(())
+}
+
+/// Base URL of an OpenAI-compatible API; `/chat/completions` is appended to it when refining
+pub fn validate_base_url(url: &str) -> Result<(), String> {
+    validate_endpoint(url)?;
+    if url.trim().trim_end_matches('/').ends_with("/chat/completions") {
+        return Err("enter the base URL, without /chat/completions".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_model(model: &str) -> Result<(), String> {
+    let model = model.trim();
+    if model.is_empty() {
+        return Err("cannot be empty".to_string());
+    }
+    if model.chars().any(char::is_whitespace) {
+        return Err("cannot contain spaces".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base_url_rejects_full_chat_endpoint() {
+        assert!(validate_base_url("https://api.groq.com/openai/v1").is_ok());
+        assert!(validate_base_url(" http://localhost:11434/v1/ ").is_ok());
+        assert!(validate_base_url("https://api.openai.com/v1/chat/completions").is_err());
+        assert!(validate_base_url("https://api.openai.com/v1/chat/completions/").is_err());
+        assert!(validate_base_url("localhost:11434").is_err());
+    }
+
+    #[test]
+    fn bad_hotkeys_do_not_reset_other_settings() {
+        let json = r#"{
+            "groq_api_key": "gsk_kept",
+            "hotkeys": ["F8", "Ctrl+Hyper+Q", ""],
+            "cancel_hotkey": "NotAKey",
+            "refine_modes": [{ "name": "Clean", "prompt": "Tidy up", "hotkey": "Alt+???" }]
+        }"#;
+        let settings: AppSettings = serde_json::from_str(json).unwrap();
+        assert_eq!(settings.groq_api_key, "gsk_kept");
+        assert_eq!(settings.hotkeys, ["F8".parse::<Hotkey>().unwrap()]);
+        assert_eq!(settings.cancel_hotkey, None);
+        assert_eq!(settings.refine_modes[0].hotkey, None);
+
+        let json = r#"{ "paste_shortcuts": { "Wtype": "Ctrl+Shift+V", "XTest": "Ctrl+Nope" } }"#;
+        let settings: AppSettings = serde_json::from_str(json).unwrap();
+        assert_eq!(settings.paste_shortcut(InjectorKind::Wtype).to_string(), "Ctrl+Shift+V");
+        assert_eq!(settings.paste_shortcut(InjectorKind::XTest).to_string(), "Ctrl+V");
+    }
+
+    #[test]
+    fn hotkeys_survive_a_save_and_load() {
+        let settings = AppSettings {
+            cancel_hotkey: None,
+            ..AppSettings::default()
+        };
+        let json = serde_json::to_string(&settings).unwrap();
+        let loaded: AppSettings = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.hotkeys, settings.hotkeys);
+        assert_eq!(loaded.cancel_hotkey, None);
+    }
+}
@@ -0,0 +1,243 @@
+//! Voice activity detection used to trim silence before upload
+//! Frames are classified by short-term energy, with zero-crossing rate rescuing quiet fricatives.
+
+/// Length of one analysis frame
+const FRAME_MS: u32 = 20;
+/// Quiet frames this far below the threshold still count as speech if they look like fricatives
+const FRICATIVE_MARGIN_DB: f32 = 8.0;
+/// Zero-crossing rate (crossings per sample) typical of unvoiced consonants like "s" and "f"
+const FRICATIVE_ZCR: f32 = 0.25;
+
+#[derive(Clone, Copy, Debug)]
+pub struct VadConfig {
+    /// Frames louder than this (dBFS) are treated as speech
+    pub threshold_db: f32,
+    /// Pauses longer than this are shortened to this length
+    pub max_gap_ms: u32,
+    /// Audio kept around each speech region so word edges are not clipped
+    pub padding_ms: u32,
+}
+
+/// Drop leading/trailing silence and collapse long pauses.
+/// Returns `None` when no frame qualifies as speech.
+pub fn trim_silence(samples: &[i16], sample_rate: u32, config: &VadConfig) -> Option<Vec<i16>> {
+    let frame_len = (sample_rate * FRAME_MS / 1000).max(1) as usize;
+    let frames: Vec<&[i16]> = samples.chunks(frame_len).collect();
+
+    let mut is_speech: Vec<bool> = frames.iter().map(|f| classify_frame(f, config.threshold_db)).collect();
+    if !is_speech.iter().any(|&s| s) {
+        return None;
+    }
+
+    // Dilate speech regions by the padding on both sides
+    let pad_frames = (config.padding_ms / FRAME_MS) as usize;
+    let speech = is_speech.clone();
+    for (i, _) in speech.iter().enumerate().filter(|(_, &s)| s) {
+        let start = i.saturating_sub(pad_frames);
+        let end = (i + pad_frames + 1).min(is_speech.len());
+        is_speech[start..end].iter_mut().for_each(|s| *s = true);
+    }
+
+    let first = is_speech.iter().position(|&s| s)?;
+    let last = is_speech.iter().rposition(|&s| s)?;
+    let max_gap_frames = (config.max_gap_ms / FRAME_MS).max(1) as usize;
+
+    let mut output = Vec::with_capacity(samples.len());
+    let mut i = first;
+    while i <= last {
+        if is_speech[i] {
+            output.extend_from_slice(frames[i]);
+            i += 1;
+            continue;
+        }
+
+        // Keep the edges of a long pause and cut out its middle
+        let gap_end = (i..=last).find(|&j| is_speech[j]).unwrap_or(last + 1);
+        let gap_len = gap_end - i;
+        if gap_len <= max_gap_frames {
+            frames[i..gap_end].iter().for_each(|f| output.extend_from_slice(f));
+        } else {
+            let head = max_gap_frames / 2;
+            let tail = max_gap_frames - head;
+            frames[i..i + head].iter().for_each(|f| output.extend_from_slice(f));
+            frames[gap_end - tail..gap_end].iter().for_each(|f| output.extend_from_slice(f));
+        }
+        i = gap_end;
+    }
+
+    Some(output)
+}
+
+fn classify_frame(frame: &[i16], threshold_db: f32) -> bool {
+    let energy_db = frame_db(frame);
+    if energy_db >= threshold_db {
+        return true;
+    }
+    energy_db >= threshold_db - FRICATIVE_MARGIN_DB && zero_crossing_rate(frame) >= FRICATIVE_ZCR
+}
+
+/// RMS level of a frame (or whole recording) in dBFS
+pub fn frame_db(frame: &[i16]) -> f32 {
+    if frame.is_empty() {
+        return f32::NEG_INFINITY;
+    }
+    let sum_sq: f64 = frame.iter().map(|&s| (s as f64) * (s as f64)).sum();
+    let rms = (sum_sq / frame.len() as f64).sqrt() / 32768.0;
+    20.0 * (rms.max(1e-10) as f32).log10()
+}
+
+fn zero_crossing_rate(frame: &[i16]) -> f32 {
+    if frame.len() < 2 {
+        return 0.0;
+    }
+    let crossings = frame.windows(2).filter(|w| (w[0] >= 0) != (w[1] >= 0)).count();
+    crossings as f32 / (frame.len() - 1) as f32
+}
+
+/// Settings for cutting a live recording into chunks at natural pauses
+#[derive(Clone, Copy, Debug)]
+pub struct ChunkerConfig {
+    /// Frames quieter than this (dBFS) count as pause
+    pub threshold_db: f32,
+    /// Never cut a chunk shorter than this
+    pub min_chunk_ms: u32,
+    /// Force a cut when no pause shows up within this long
+    pub max_chunk_ms: u32,
+    /// Silence needed before a cut is made in the middle of it
+    pub pause_ms: u32,
+}
+
+/// Incremental pause detector over a growing sample buffer; only new frames are examined per call
+pub struct PauseChunker {
+    config: ChunkerConfig,
+    frame_len: usize,
+    sample_rate: u32,
+    chunk_start: usize,
+    scanned: usize,
+    silence_start: Option<usize>,
+}
+
+impl PauseChunker {
+    pub fn new(config: ChunkerConfig, sample_rate: u32) -> Self {
+        Self {
+            config,
+            frame_len: (sample_rate * FRAME_MS / 1000).max(1) as usize,
+            sample_rate,
+            chunk_start: 0,
+            scanned: 0,
+            silence_start: None,
+        }
+    }
+
+    /// Start of the chunk that has not been emitted yet
+    pub fn chunk_start(&self) -> usize {
+        self.chunk_start
+    }
+
+    /// Scan newly recorded frames and return the next cut position, if one was found
+    pub fn next_cut(&mut self, samples: &[i16]) -> Option<usize> {
+        let min_len = self.ms_to_samples(self.config.min_chunk_ms);
+        let max_len = self.ms_to_samples(self.config.max_chunk_ms);
+        let pause_len = self.ms_to_samples(self.config.pause_ms);
+
+        while self.scanned + self.frame_len <= samples.len() {
+            let frame_start = self.scanned;
+            let frame = &samples[frame_start..frame_start + self.frame_len];
+            self.scanned += self.frame_len;
+
+            if frame_db(frame) < self.config.threshold_db {
+                self.silence_start.get_or_insert(frame_start);
+            } else {
+                self.silence_start = None;
+            }
+
+            let chunk_len = self.scanned - self.chunk_start;
+            let cut = match self.silence_start {
+                Some(start) if self.scanned - start >= pause_len && chunk_len >= min_len => {
+                    Some(start + (self.scanned - start) / 2)
+                }
+                _ if chunk_len >= max_len => Some(self.scanned),
+                _ => None,
+            };
+
+            if let Some(cut) = cut {
+                self.chunk_start = cut;
+                self.silence_start = None;
+                return Some(cut);
+            }
+        }
+        None
+    }
+
+    fn ms_to_samples(&self, ms: u32) -> usize {
+        (self.sample_rate as u64 * ms as u64 / 1000) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{trim_silence, VadConfig};
+
+    const RATE: u32 = 16_000;
+    const FRAME: usize = 320;
+
+    fn config(max_gap_ms: u32, padding_ms: u32) -> VadConfig {
+        VadConfig { threshold_db: -40.0, max_gap_ms, padding_ms }
+    }
+
+    fn tone(ms: usize) -> Vec<i16> {
+        (0..RATE as usize * ms / 1000)
+            .map(|i| ((i as f32 * 440.0 * std::f32::consts::TAU / RATE as f32).sin() * 8000.0) as i16)
+            .collect()
+    }
+
+    fn silence(ms: usize) -> Vec<i16> {
+        vec![0; RATE as usize * ms / 1000]
+    }
+
+    #[test]
+    fn keeps_padding_around_speech() {
+        let speech = tone(200);
+        let samples = [silence(500), speech.clone(), silence(500)].concat();
+
+        let trimmed = trim_silence(&samples, RATE, &config(1000, 100)).unwrap();
+
+        // 100 ms (5 frames) of silence on each side of the 10 speech frames
+        assert_eq!(trimmed.len(), 20 * FRAME);
+        assert!(trimmed[..5 * FRAME].iter().all(|&s| s == 0));
+        assert_eq!(&trimmed[5 * FRAME..15 * FRAME], &speech[..]);
+        assert!(trimmed[15 * FRAME..].iter().all(|&s| s == 0));
+    }
+
+    #[test]
+    fn collapses_long_pauses_to_max_gap() {
+        let first = tone(200);
+        let second = tone(200);
+        let samples = [first.clone(), silence(2000), second.clone()].concat();
+
+        let trimmed = trim_silence(&samples, RATE, &config(300, 0)).unwrap();
+
+        // The 100-frame pause shrinks to 300 ms (15 frames)
+        assert_eq!(trimmed.len(), (10 + 15 + 10) * FRAME);
+        assert_eq!(&trimmed[..10 * FRAME], &first[..]);
+        assert!(trimmed[10 * FRAME..25 * FRAME].iter().all(|&s| s == 0));
+        assert_eq!(&trimmed[25 * FRAME..], &second[..]);
+    }
+
+    #[test]
+    fn short_pauses_are_kept_whole() {
+        let samples = [tone(200), silence(200), tone(200)].concat();
+
+        let trimmed = trim_silence(&samples, RATE, &config(300, 0)).unwrap();
+
+        assert_eq!(trimmed, samples);
+    }
+
+    #[test]
+    fn all_silent_input_returns_none() {
+        assert_eq!(trim_silence(&silence(1000), RATE, &config(300, 100)), None);
+        let hum: Vec<i16> = (0..RATE as usize).map(|i| if i % 64 < 32 { 20 } else { -20 }).collect();
+        assert_eq!(trim_silence(&hum, RATE, &config(300, 100)), None);
+        assert_eq!(trim_silence(&[], RATE, &config(300, 100)), None);
+    }
+}